@@ -0,0 +1,484 @@
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    fmt,
+    hash::Hash,
+    ops::Range,
+    str::FromStr,
+};
+
+/// A cell position in an automaton.
+pub trait Coordinate: Copy + Eq + Hash {
+    /// Tracks the extent of every cell that has ever been active.
+    type Bounds: Clone + fmt::Debug;
+
+    fn neighbours(self) -> impl Iterator<Item = Self>;
+    fn empty_bounds() -> Self::Bounds;
+    fn expand(self, bounds: &mut Self::Bounds);
+}
+
+/// A point on an `N` dimensional integer lattice, neighbouring the `3^N - 1` points that differ
+/// by at most one in every axis.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Point<const N: usize>(pub [i32; N]);
+
+impl<const N: usize> Coordinate for Point<N> {
+    type Bounds = [Range<i32>; N];
+
+    fn neighbours(self) -> impl Iterator<Item = Self> {
+        let count = 3usize.pow(N as u32);
+        let centre = count / 2;
+        (0..count).filter(move |&i| i != centre).map(move |mut i| {
+            let mut point = self;
+            for coord in &mut point.0 {
+                *coord += (i % 3) as i32 - 1;
+                i /= 3;
+            }
+            point
+        })
+    }
+
+    fn empty_bounds() -> Self::Bounds {
+        std::array::from_fn(|_| 0..0)
+    }
+
+    fn expand(self, bounds: &mut Self::Bounds) {
+        for (range, &value) in bounds.iter_mut().zip(&self.0) {
+            if range.start >= range.end {
+                *range = value..(value + 1);
+            } else if value < range.start {
+                range.start = value;
+            } else if value >= range.end {
+                range.end = value + 1
+            }
+        }
+    }
+}
+
+/// Which neighbour counts bring a cell to life and which keep it alive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rule {
+    birth: BTreeSet<usize>,
+    survive: BTreeSet<usize>,
+}
+
+impl Rule {
+    /// Births with no neighbours can't be supported by a sparse automaton, so `birth` must not
+    /// contain 0.
+    pub fn new(
+        birth: impl IntoIterator<Item = usize>,
+        survive: impl IntoIterator<Item = usize>,
+    ) -> Self {
+        let birth: BTreeSet<_> = birth.into_iter().collect();
+        assert!(!birth.contains(&0), "B0 rules are not supported");
+        Self {
+            birth,
+            survive: survive.into_iter().collect(),
+        }
+    }
+
+    /// B3/S23, the rules of Conway's Game of Life.
+    pub fn conway() -> Self {
+        Self::new([3], [2, 3])
+    }
+
+    pub fn next_state(&self, active: bool, neighbours: usize) -> bool {
+        if active {
+            self.survive.contains(&neighbours)
+        } else {
+            self.birth.contains(&neighbours)
+        }
+    }
+}
+
+impl FromStr for Rule {
+    type Err = String;
+
+    /// Parses rules like `B36/S23`. Counts above 9 can be written by separating every count with
+    /// commas, as in `B3/S2,3,10`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse_counts = |counts: &str| -> Result<Vec<usize>, String> {
+            if counts.contains(',') {
+                counts
+                    .split(',')
+                    .map(|count| {
+                        count
+                            .trim()
+                            .parse()
+                            .map_err(|_| format!("Invalid neighbour count {:?}", count))
+                    })
+                    .collect()
+            } else {
+                counts
+                    .chars()
+                    .map(|c| {
+                        c.to_digit(10)
+                            .map(|count| count as usize)
+                            .ok_or_else(|| format!("Invalid neighbour count {:?}", c))
+                    })
+                    .collect()
+            }
+        };
+        let (birth, survive) = s
+            .split_once('/')
+            .ok_or_else(|| format!("Expected a rule like B3/S23, got {:?}", s))?;
+        let birth = birth
+            .strip_prefix('B')
+            .ok_or_else(|| format!("Expected birth counts starting with 'B', got {:?}", birth))?;
+        let survive = survive.strip_prefix('S').ok_or_else(|| {
+            format!(
+                "Expected survival counts starting with 'S', got {:?}",
+                survive
+            )
+        })?;
+        let birth = parse_counts(birth)?;
+        if birth.contains(&0) {
+            return Err("B0 rules are not supported".to_owned());
+        }
+        Ok(Self::new(birth, parse_counts(survive)?))
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let write_counts = |f: &mut fmt::Formatter<'_>, counts: &BTreeSet<usize>| {
+            let separator = if counts.iter().any(|&count| count > 9) {
+                ","
+            } else {
+                ""
+            };
+            let counts: Vec<_> = counts.iter().map(ToString::to_string).collect();
+            f.write_str(&counts.join(separator))
+        };
+        f.write_str("B")?;
+        write_counts(f, &self.birth)?;
+        f.write_str("/S")?;
+        write_counts(f, &self.survive)
+    }
+}
+
+/// A cellular automaton on an unbounded space, storing only the active cells.
+#[derive(Clone)]
+pub struct Automaton<C: Coordinate> {
+    active: HashSet<C>,
+    bounds: C::Bounds,
+    rule: Rule,
+}
+
+impl<C: Coordinate> Automaton<C> {
+    pub fn new(rule: Rule, active: impl IntoIterator<Item = C>) -> Self {
+        let mut automaton = Self {
+            active: HashSet::new(),
+            bounds: C::empty_bounds(),
+            rule,
+        };
+        for cell in active {
+            automaton.set(cell, true);
+        }
+        automaton
+    }
+
+    pub fn rule(&self) -> &Rule {
+        &self.rule
+    }
+
+    pub fn active(&self) -> &HashSet<C> {
+        &self.active
+    }
+
+    pub fn len(&self) -> usize {
+        self.active.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.active.is_empty()
+    }
+
+    pub fn is_active(&self, cell: C) -> bool {
+        self.active.contains(&cell)
+    }
+
+    /// The extent of every cell that has been active, which never shrinks.
+    pub fn bounds(&self) -> &C::Bounds {
+        &self.bounds
+    }
+
+    pub fn set(&mut self, cell: C, active: bool) {
+        if active {
+            self.active.insert(cell);
+            cell.expand(&mut self.bounds);
+        } else {
+            self.active.remove(&cell);
+        }
+    }
+
+    /// Only visits the active cells, counting how many active neighbours every other cell has.
+    pub fn step(&self) -> Self {
+        let mut counts = HashMap::new();
+        for &cell in &self.active {
+            for neighbour in cell.neighbours() {
+                *counts.entry(neighbour).or_insert(0) += 1;
+            }
+        }
+        self.next_generation(&counts)
+    }
+
+    fn next_generation(&self, counts: &HashMap<C, usize>) -> Self {
+        let mut new = Self {
+            active: HashSet::with_capacity(self.active.len()),
+            bounds: self.bounds.clone(),
+            rule: self.rule.clone(),
+        };
+        for &cell in &self.active {
+            let count = counts.get(&cell).copied().unwrap_or(0);
+            if self.rule.next_state(true, count) {
+                new.set(cell, true);
+            }
+        }
+        for (&cell, &count) in counts {
+            if !self.is_active(cell) && self.rule.next_state(false, count) {
+                new.set(cell, true);
+            }
+        }
+        new
+    }
+
+    pub fn run(&self, generations: usize) -> Self {
+        let mut automaton = self.clone();
+        for _ in 0..generations {
+            automaton = automaton.step();
+        }
+        automaton
+    }
+}
+
+/// An automaton whose state is unchanged by negating any of the axes from `first_axis` onwards,
+/// such as one started from a single x-y slice. Only the cells with non-negative coordinates on
+/// those axes are stored, which divides the work by up to a factor of two per mirrored axis.
+#[derive(Clone)]
+pub struct MirroredAutomaton<const N: usize> {
+    half: Automaton<Point<N>>,
+    first_axis: usize,
+}
+
+impl<const N: usize> MirroredAutomaton<N> {
+    /// Returns `None` if the automaton isn't symmetric in the mirrored axes.
+    pub fn new(automaton: &Automaton<Point<N>>, first_axis: usize) -> Option<Self> {
+        let symmetric = automaton
+            .active
+            .iter()
+            .all(|&cell| Self::images(cell, first_axis).all(|image| automaton.is_active(image)));
+        if !symmetric {
+            return None;
+        }
+        let half = automaton
+            .active
+            .iter()
+            .copied()
+            .filter(|cell| cell.0[first_axis..].iter().all(|&coord| coord >= 0));
+        Some(Self {
+            half: Automaton::new(automaton.rule.clone(), half),
+            first_axis,
+        })
+    }
+
+    /// Every cell made by negating some of the mirrored axes of `cell`, including itself.
+    fn images(cell: Point<N>, first_axis: usize) -> impl Iterator<Item = Point<N>> {
+        let axes = (first_axis..N).filter(|&axis| cell.0[axis] != 0).collect();
+        Self::reflections(cell, axes)
+    }
+
+    fn reflections(cell: Point<N>, axes: Vec<usize>) -> impl Iterator<Item = Point<N>> {
+        (0..1usize << axes.len()).map(move |flips| {
+            let mut image = cell;
+            for (i, &axis) in axes.iter().enumerate() {
+                if flips & (1 << i) != 0 {
+                    image.0[axis] = -image.0[axis];
+                }
+            }
+            image
+        })
+    }
+
+    fn is_stored(&self, cell: Point<N>) -> bool {
+        cell.0[self.first_axis..].iter().all(|&coord| coord >= 0)
+    }
+
+    pub fn len(&self) -> usize {
+        self.half
+            .active
+            .iter()
+            .map(|cell| {
+                let mirrored = cell.0[self.first_axis..]
+                    .iter()
+                    .filter(|&&coord| coord != 0)
+                    .count();
+                1 << mirrored
+            })
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.half.is_empty()
+    }
+
+    pub fn step(&self) -> Self {
+        let mut counts = HashMap::new();
+        for &cell in &self.half.active {
+            // Only images reflected from a coordinate of 1 to -1 can neighbour a stored cell.
+            let axes = (self.first_axis..N)
+                .filter(|&axis| cell.0[axis] == 1)
+                .collect();
+            for image in Self::reflections(cell, axes) {
+                for neighbour in image.neighbours() {
+                    if self.is_stored(neighbour) {
+                        *counts.entry(neighbour).or_insert(0) += 1;
+                    }
+                }
+            }
+        }
+        Self {
+            half: self.half.next_generation(&counts),
+            first_axis: self.first_axis,
+        }
+    }
+
+    pub fn run(&self, generations: usize) -> Self {
+        let mut automaton = self.clone();
+        for _ in 0..generations {
+            automaton = automaton.step();
+        }
+        automaton
+    }
+
+    /// The full automaton, with every mirrored cell filled back in.
+    pub fn unfold(&self) -> Automaton<Point<N>> {
+        Automaton::new(
+            self.half.rule.clone(),
+            self.half
+                .active
+                .iter()
+                .flat_map(|&cell| Self::images(cell, self.first_axis)),
+        )
+    }
+}
+
+impl<const N: usize> fmt::Debug for MirroredAutomaton<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.unfold().fmt(f)
+    }
+}
+
+const AXES: [&str; 6] = ["x", "y", "z", "w", "v", "u"];
+
+/// Prints every x-y slice with y increasing upwards, labelled with the coordinates of the slice.
+impl<const N: usize> fmt::Debug for Automaton<Point<N>> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if N < 2 || self.bounds.iter().any(|range| range.start >= range.end) {
+            return Ok(());
+        }
+        let mut slice: Vec<i32> = self.bounds[2..].iter().map(|range| range.start).collect();
+        loop {
+            let labels: Vec<_> = slice
+                .iter()
+                .enumerate()
+                .map(|(i, coord)| match AXES.get(i + 2) {
+                    Some(axis) => format!("{}={}", axis, coord),
+                    None => format!("x{}={}", i + 2, coord),
+                })
+                .collect();
+            if !labels.is_empty() {
+                writeln!(f, "{}", labels.join(", "))?;
+            }
+            for y in self.bounds[1].clone().rev() {
+                for x in self.bounds[0].clone() {
+                    let mut point = [0; N];
+                    point[0] = x;
+                    point[1] = y;
+                    point[2..].copy_from_slice(&slice);
+                    f.write_str(if self.is_active(Point(point)) {
+                        "#"
+                    } else {
+                        "."
+                    })?;
+                }
+                writeln!(f)?;
+            }
+            // Advance the slice coordinates like an odometer, with the last axis outermost.
+            let mut axis = 0;
+            loop {
+                match slice.get_mut(axis) {
+                    None => return Ok(()),
+                    Some(coord) => {
+                        *coord += 1;
+                        if *coord < self.bounds[axis + 2].end {
+                            break;
+                        }
+                        *coord = self.bounds[axis + 2].start;
+                        axis += 1;
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rule_notation() {
+        let rule: Rule = "B36/S23".parse().unwrap();
+        assert_eq!(Rule::new([3, 6], [2, 3]), rule);
+        assert_eq!("B36/S23", rule.to_string());
+        let rule: Rule = "B3/S2,3,10".parse().unwrap();
+        assert_eq!(Rule::new([3], [2, 3, 10]), rule);
+        assert_eq!("B3/S2,3,10", rule.to_string());
+        assert!("B03/S23".parse::<Rule>().is_err());
+        assert!("S23/B3".parse::<Rule>().is_err());
+    }
+
+    #[test]
+    fn neighbours() {
+        assert_eq!(8, Point([0, 0]).neighbours().count());
+        assert_eq!(80, Point([0, 0, 0, 0]).neighbours().count());
+        let neighbours: HashSet<_> = Point([5, -5, 0]).neighbours().collect();
+        assert_eq!(26, neighbours.len());
+        assert!(!neighbours.contains(&Point([5, -5, 0])));
+        assert!(neighbours.contains(&Point([4, -4, 1])));
+    }
+
+    #[test]
+    fn blinker() {
+        let blinker = Automaton::new(
+            Rule::conway(),
+            vec![Point([0, 1]), Point([1, 1]), Point([2, 1])],
+        );
+        let next = blinker.step();
+        let expected: HashSet<_> = vec![Point([1, 0]), Point([1, 1]), Point([1, 2])]
+            .into_iter()
+            .collect();
+        assert_eq!(&expected, next.active());
+        assert_eq!(blinker.active(), next.step().active());
+        assert_eq!("...\n###\n...\n", format!("{:?}", next.step()));
+    }
+
+    #[test]
+    fn mirrored() {
+        let slice = [
+            Point([1, 0, 0, 0]),
+            Point([2, 1, 0, 0]),
+            Point([0, 2, 0, 0]),
+        ];
+        let mut full = Automaton::new(Rule::conway(), slice.iter().copied());
+        let mut mirrored = MirroredAutomaton::new(&full, 2).unwrap();
+        for _ in 0..4 {
+            full = full.step();
+            mirrored = mirrored.step();
+            assert_eq!(full.len(), mirrored.len());
+            assert_eq!(full.active(), mirrored.unfold().active());
+        }
+        let lopsided = Automaton::new(Rule::conway(), vec![Point([0, 0, 1])]);
+        assert!(MirroredAutomaton::new(&lopsided, 2).is_none());
+    }
+}
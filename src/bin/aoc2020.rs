@@ -0,0 +1,249 @@
+use std::{
+    env, fs,
+    io::{self, Read},
+    process,
+};
+
+use aoc2020::{day17, solve, trim_input, RunError};
+use serde_json::json;
+
+const USAGE: &str = "\
+Usage: aoc2020 --day <DAY> [--part <PART>] [--json] [INPUT]
+
+Solves an Advent of Code 2020 puzzle, reading the puzzle input from the
+INPUT file, or from stdin if INPUT is omitted or is \"-\".
+
+Options:
+    -d, --day <DAY>     Day to solve (1-25)
+    -p, --part <PART>   Part to solve (1 or 2), both parts if omitted
+        --json          Print one JSON object per part instead of plain text
+    -h, --help          Print this help message
+
+Day 17 options:
+        --dimensions <N>  Simulate N dimensions (2-8) instead of 3 for part 1
+                          and 4 for part 2
+        --cycles <N>      Run N cycles instead of 6
+        --dump            Print every generation's slices to stderr
+";
+
+#[derive(Debug)]
+struct Args {
+    day: u8,
+    parts: Vec<u8>,
+    json: bool,
+    input: Option<String>,
+    simulation: Option<Simulation>,
+}
+
+/// Day 17 runs with a non-standard shape.
+#[derive(Debug)]
+struct Simulation {
+    dimensions: Vec<usize>,
+    cycles: usize,
+    dump: bool,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut day = None;
+    let mut part = None;
+    let mut json = false;
+    let mut input = None;
+    let mut dimensions = None;
+    let mut cycles = None;
+    let mut dump = false;
+    while let Some(arg) = args.next() {
+        let (flag, value) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => {
+                (flag.to_owned(), Some(value.to_owned()))
+            }
+            _ => (arg, None),
+        };
+        let mut value = |name: &str| {
+            value
+                .clone()
+                .or_else(|| args.next())
+                .ok_or_else(|| format!("{} requires a value", name))
+        };
+        match flag.as_str() {
+            "-h" | "--help" => {
+                print!("{}", USAGE);
+                process::exit(0);
+            }
+            "-d" | "--day" => {
+                let d = value("--day")?;
+                day = Some(
+                    d.parse()
+                        .ok()
+                        .filter(|d| (1..=25).contains(d))
+                        .ok_or_else(|| format!("Invalid day {:?}, expected 1-25", d))?,
+                );
+            }
+            "-p" | "--part" => {
+                let p = value("--part")?;
+                part = Some(
+                    p.parse()
+                        .ok()
+                        .filter(|p| (1..=2).contains(p))
+                        .ok_or_else(|| format!("Invalid part {:?}, expected 1 or 2", p))?,
+                );
+            }
+            "--json" => json = true,
+            "--dimensions" => {
+                let d = value("--dimensions")?;
+                dimensions = Some(
+                    d.parse()
+                        .ok()
+                        .filter(|d| day17::DIMENSIONS.contains(d))
+                        .ok_or_else(|| {
+                            format!(
+                                "Invalid dimension count {:?}, expected {}-{}",
+                                d,
+                                day17::DIMENSIONS.start(),
+                                day17::DIMENSIONS.end()
+                            )
+                        })?,
+                );
+            }
+            "--cycles" => {
+                let c = value("--cycles")?;
+                cycles = Some(
+                    c.parse()
+                        .map_err(|_| format!("Invalid cycle count {:?}", c))?,
+                );
+            }
+            "--dump" => dump = true,
+            flag if flag.starts_with('-') && flag != "-" => {
+                return Err(format!("Unknown option {}", flag))
+            }
+            path => {
+                if input.replace(path.to_owned()).is_some() {
+                    return Err("Only one input file can be given".to_owned());
+                }
+            }
+        }
+    }
+    let day = day.ok_or_else(|| "--day is required".to_owned())?;
+    let parts = match part {
+        Some(part) => vec![part],
+        None if day == 25 => vec![1],
+        None => vec![1, 2],
+    };
+    let simulation = if dimensions.is_some() || cycles.is_some() || dump {
+        if day != 17 {
+            return Err("--dimensions, --cycles and --dump only apply to day 17".to_owned());
+        }
+        Some(Simulation {
+            dimensions: match dimensions {
+                Some(dimensions) => vec![dimensions],
+                None => parts.iter().map(|&part| usize::from(part) + 2).collect(),
+            },
+            cycles: cycles.unwrap_or(6),
+            dump,
+        })
+    } else {
+        None
+    };
+    Ok(Args {
+        day,
+        parts,
+        json,
+        input: input.filter(|path| path != "-"),
+        simulation,
+    })
+}
+
+fn read_input(path: Option<&str>) -> io::Result<String> {
+    match path {
+        Some(path) => fs::read_to_string(path),
+        None => {
+            let mut input = String::new();
+            io::stdin().read_to_string(&mut input)?;
+            Ok(input)
+        }
+    }
+}
+
+fn simulate(input: &str, dimensions: usize, simulation: &Simulation) -> Result<String, RunError> {
+    let grid = day17::input_generator(trim_input(input))?;
+    let count = day17::simulate(&grid, dimensions, simulation.cycles, |cycle, cube| {
+        if simulation.dump {
+            eprintln!("After {} cycles:\n{:?}", cycle, cube);
+        }
+    })?;
+    Ok(count.to_string())
+}
+
+fn main() {
+    let args = match parse_args(env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, USAGE);
+            process::exit(2);
+        }
+    };
+    let input = match read_input(args.input.as_deref()) {
+        Ok(input) => input,
+        Err(e) => {
+            eprintln!(
+                "error: Couldn't read {}: {}",
+                args.input.as_deref().unwrap_or("stdin"),
+                e
+            );
+            process::exit(2);
+        }
+    };
+
+    let runs: Vec<_> = match &args.simulation {
+        None => args
+            .parts
+            .iter()
+            .map(|&part| {
+                (
+                    format!("Day {} part {}", args.day, part),
+                    json!({ "day": args.day, "part": part }),
+                    solve(args.day, part, &input),
+                )
+            })
+            .collect(),
+        Some(simulation) => simulation
+            .dimensions
+            .iter()
+            .map(|&dimensions| {
+                (
+                    format!(
+                        "Day 17, {} dimensions, {} cycles",
+                        dimensions, simulation.cycles
+                    ),
+                    json!({ "day": 17, "dimensions": dimensions, "cycles": simulation.cycles }),
+                    simulate(&input, dimensions, simulation),
+                )
+            })
+            .collect(),
+    };
+
+    let mut failed = false;
+    for (label, mut output, result) in runs {
+        failed |= result.is_err();
+        match (args.json, result) {
+            (false, Ok(answer)) => println!("{}: {}", label, answer),
+            (false, Err(e)) => eprintln!("{}: error: {}", label, e),
+            (true, result) => {
+                match result {
+                    Ok(answer) => output["answer"] = json!(answer),
+                    Err(e) => {
+                        let kind = match e {
+                            RunError::UnknownPuzzle { .. } => "unknown_puzzle",
+                            RunError::Parse(_) => "parse",
+                            RunError::Solve(_) => "solve",
+                        };
+                        output["error"] = json!({ "kind": kind, "message": e.to_string() });
+                    }
+                };
+                println!("{}", output);
+            }
+        }
+    }
+    if failed {
+        process::exit(1);
+    }
+}
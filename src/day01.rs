@@ -1,50 +1,313 @@
+use std::{collections::HashMap, ops::ControlFlow};
+
 use aoc_runner_derive::{aoc, aoc_generator};
-use std::cmp::Ordering;
+
+use crate::{ParseError, SolveError};
 
 #[aoc_generator(day1)]
-pub fn input_generator(input: &str) -> Vec<u32> {
-    input.lines().map(|line| line.parse().unwrap()).collect()
+pub fn input_generator(input: &str) -> Result<Vec<u32>, ParseError> {
+    input
+        .lines()
+        .map(|line| {
+            line.parse().map_err(|e| {
+                ParseError::at(1, input, line, format!("Invalid number {:?}: {}", line, e))
+            })
+        })
+        .collect()
 }
 
-#[aoc(day1, part1)]
-pub fn day1_part1(input: &[u32]) -> u32 {
-    let mut input = input.to_vec();
-    input.sort_unstable();
-    let mut start = 0;
-    let mut end = input.len() - 1;
-    loop {
-        if start >= end {
-            panic!("No pair sum to 2020.");
-        }
-        match (unsafe { input.get_unchecked(start) + input.get_unchecked(end) }).cmp(&2020) {
-            Ordering::Less => {
-                start += 1;
+/// Every set of `k` distinct indices into `values` whose values sum to `target`, each with its
+/// indices in increasing order.
+pub fn k_sum(values: &[u32], k: usize, target: u64) -> Vec<Vec<usize>> {
+    let mut found = Vec::new();
+    let _ = search(values, k, target, &mut |indices| {
+        found.push(indices.to_vec());
+        ControlFlow::Continue(())
+    });
+    found
+}
+
+/// The first of the sets `k_sum` would return, without searching for the rest.
+pub fn first_k_sum(values: &[u32], k: usize, target: u64) -> Option<Vec<usize>> {
+    let mut found = None;
+    let _ = search(values, k, target, &mut |indices| {
+        found = Some(indices.to_vec());
+        ControlFlow::Break(())
+    });
+    found
+}
+
+fn search(
+    values: &[u32],
+    k: usize,
+    target: u64,
+    visit: &mut impl FnMut(&[usize]) -> ControlFlow<()>,
+) -> ControlFlow<()> {
+    if k >= 4 {
+        meet_in_the_middle(values, k, target, visit)
+    } else {
+        fix_smallest(values, k, target, 0, &mut Vec::with_capacity(k), visit)
+    }
+}
+
+/// Chooses the smallest index of the set and searches the later values for the rest, finishing
+/// with a hash lookup for the last pair.
+fn fix_smallest(
+    values: &[u32],
+    k: usize,
+    target: u64,
+    start: usize,
+    prefix: &mut Vec<usize>,
+    visit: &mut impl FnMut(&[usize]) -> ControlFlow<()>,
+) -> ControlFlow<()> {
+    match k {
+        0 if target == 0 => visit(prefix),
+        0 => ControlFlow::Continue(()),
+        1 => {
+            for (i, &value) in values.iter().enumerate().skip(start) {
+                if u64::from(value) == target {
+                    prefix.push(i);
+                    let flow = visit(prefix);
+                    prefix.pop();
+                    flow?;
+                }
             }
-            Ordering::Equal => {
-                break input[start] * input[end];
+            ControlFlow::Continue(())
+        }
+        2 => {
+            let mut seen: HashMap<u64, Vec<usize>> = HashMap::new();
+            for (j, &value) in values.iter().enumerate().skip(start) {
+                let value = u64::from(value);
+                if let Some(earlier) = target.checked_sub(value).and_then(|rest| seen.get(&rest)) {
+                    for &i in earlier {
+                        prefix.extend([i, j]);
+                        let flow = visit(prefix);
+                        prefix.truncate(prefix.len() - 2);
+                        flow?;
+                    }
+                }
+                seen.entry(value).or_default().push(j);
             }
-            Ordering::Greater => {
-                end -= 1;
+            ControlFlow::Continue(())
+        }
+        _ => {
+            for (i, &value) in values.iter().enumerate().skip(start) {
+                if let Some(rest) = target.checked_sub(u64::from(value)) {
+                    prefix.push(i);
+                    let flow = fix_smallest(values, k - 1, rest, i + 1, prefix, visit);
+                    prefix.pop();
+                    flow?;
+                }
             }
+            ControlFlow::Continue(())
         }
     }
 }
 
+/// Splits every set into its smallest `k / 2` indices and the rest, and pairs up the halves
+/// whose sums add up to `target`.
+fn meet_in_the_middle(
+    values: &[u32],
+    k: usize,
+    target: u64,
+    visit: &mut impl FnMut(&[usize]) -> ControlFlow<()>,
+) -> ControlFlow<()> {
+    let mut lower_halves: HashMap<u64, Vec<Vec<usize>>> = HashMap::new();
+    let _ = combinations(values, k / 2, &mut Vec::new(), 0, 0, &mut |half, sum| {
+        if sum <= target {
+            lower_halves.entry(sum).or_default().push(half.to_vec());
+        }
+        ControlFlow::Continue(())
+    });
+    let mut indices = Vec::with_capacity(k);
+    combinations(
+        values,
+        k - k / 2,
+        &mut Vec::new(),
+        0,
+        0,
+        &mut |upper, sum| {
+            let lower = target
+                .checked_sub(sum)
+                .and_then(|rest| lower_halves.get(&rest));
+            for lower in lower.into_iter().flatten() {
+                if lower.last() < upper.first() {
+                    indices.clear();
+                    indices.extend_from_slice(lower);
+                    indices.extend_from_slice(upper);
+                    visit(&indices)?;
+                }
+            }
+            ControlFlow::Continue(())
+        },
+    )
+}
+
+fn combinations(
+    values: &[u32],
+    k: usize,
+    prefix: &mut Vec<usize>,
+    start: usize,
+    sum: u64,
+    visit: &mut impl FnMut(&[usize], u64) -> ControlFlow<()>,
+) -> ControlFlow<()> {
+    if k == 0 {
+        return visit(prefix, sum);
+    }
+    for (i, &value) in values.iter().enumerate().skip(start) {
+        prefix.push(i);
+        let flow = combinations(values, k - 1, prefix, i + 1, sum + u64::from(value), visit);
+        prefix.pop();
+        flow?;
+    }
+    ControlFlow::Continue(())
+}
+
+fn product_of_k_sum(input: &[u32], k: usize) -> Result<u32, SolveError> {
+    let indices = first_k_sum(input, k, 2020)
+        .ok_or_else(|| SolveError::NoSolution(format!("No {} entries sum to 2020", k)))?;
+    Ok(indices.iter().map(|&i| input[i]).product())
+}
+
+#[aoc(day1, part1)]
+pub fn day1_part1(input: &[u32]) -> Result<u32, SolveError> {
+    product_of_k_sum(input, 2)
+}
+
 #[aoc(day1, part2)]
-pub fn day1_part2(input: &[u32]) -> u32 {
-    let mut input = input.to_vec();
-    input.sort_unstable();
-    for (a_idx, &a) in input.iter().enumerate() {
-        let mut last_c_idx = input.len();
-        for &b in input.iter().skip(a_idx + 1) {
-            if a + b >= 2020 {
-                break;
+pub fn day1_part2(input: &[u32]) -> Result<u32, SolveError> {
+    product_of_k_sum(input, 3)
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn parse_error_location() {
+        let err = input_generator("1721\n979\n366 \n299\n").unwrap_err();
+        assert_eq!((1, 3, 1), (err.day, err.line, err.column));
+    }
+
+    const EXAMPLE: [u32; 6] = [1721, 979, 366, 299, 675, 1456];
+
+    fn brute_force(values: &[u32], k: usize, target: u64) -> Vec<Vec<usize>> {
+        fn extend(
+            values: &[u32],
+            k: usize,
+            target: u64,
+            indices: &mut Vec<usize>,
+            found: &mut Vec<Vec<usize>>,
+        ) {
+            if indices.len() == k {
+                if indices.iter().map(|&i| u64::from(values[i])).sum::<u64>() == target {
+                    found.push(indices.clone());
+                }
+                return;
             }
-            match input[..last_c_idx].binary_search(&(2020 - a - b)) {
-                Ok(c) => return a * b * input[c],
-                Err(c_idx) => last_c_idx = c_idx,
+            let start = indices.last().map_or(0, |&i| i + 1);
+            for i in start..values.len() {
+                indices.push(i);
+                extend(values, k, target, indices, found);
+                indices.pop();
+            }
+        }
+        let mut found = Vec::new();
+        extend(values, k, target, &mut Vec::new(), &mut found);
+        found
+    }
+
+    #[test]
+    fn example() {
+        assert_eq!(vec![vec![0, 3]], k_sum(&EXAMPLE, 2, 2020));
+        assert_eq!(vec![vec![1, 2, 4]], k_sum(&EXAMPLE, 3, 2020));
+        assert_eq!(Ok(514579), day1_part1(&EXAMPLE));
+        assert_eq!(Ok(241861950), day1_part2(&EXAMPLE));
+    }
+
+    #[test]
+    fn k_sum_matches_brute_force() {
+        let values = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 5, 5, 0, 12];
+        for k in 0..=7 {
+            for target in [0, 5, 10, 15, 21, 30] {
+                let mut found = k_sum(&values, k, target);
+                found.sort();
+                assert_eq!(
+                    brute_force(&values, k, target),
+                    found,
+                    "k={} target={}",
+                    k,
+                    target
+                );
+                assert_eq!(found.is_empty(), first_k_sum(&values, k, target).is_none());
             }
         }
     }
-    panic!("No triplet sum to 2020")
+
+    #[test]
+    fn degenerate_inputs() {
+        assert!(matches!(day1_part1(&[]), Err(SolveError::NoSolution(_))));
+        assert!(matches!(day1_part2(&[]), Err(SolveError::NoSolution(_))));
+        assert!(matches!(
+            day1_part1(&[2020]),
+            Err(SolveError::NoSolution(_))
+        ));
+        assert!(matches!(
+            day1_part1(&[1010]),
+            Err(SolveError::NoSolution(_))
+        ));
+        assert!(matches!(
+            day1_part2(&[1010, 1010]),
+            Err(SolveError::NoSolution(_))
+        ));
+        assert_eq!(Ok(1010 * 1010), day1_part1(&[3, 1010, 7, 1010]));
+        assert_eq!(Ok(0), day1_part2(&[0, 1010, 1010]));
+    }
+
+    /// The products of every set of `k` entries summing to 2020.
+    fn brute_force_products(values: &[u32], k: usize) -> Vec<u32> {
+        brute_force(values, k, 2020)
+            .iter()
+            .map(|indices| indices.iter().map(|&i| values[i]).product())
+            .collect()
+    }
+
+    proptest! {
+        #[test]
+        fn k_sum_is_exhaustive(
+            values in prop::collection::vec(0u32..20, 0..12),
+            k in 0usize..7,
+            target in 0u64..80,
+        ) {
+            let mut found = k_sum(&values, k, target);
+            found.sort();
+            prop_assert_eq!(brute_force(&values, k, target), found);
+        }
+
+        #[test]
+        fn solvers_match_brute_force(values in prop::collection::vec(0u32..=2020, 0..30)) {
+            for (k, result) in [(2, day1_part1(&values)), (3, day1_part2(&values))] {
+                let products = brute_force_products(&values, k);
+                match result {
+                    Ok(product) => prop_assert!(products.contains(&product)),
+                    Err(e) => {
+                        prop_assert!(products.is_empty());
+                        prop_assert!(matches!(e, SolveError::NoSolution(_)));
+                    }
+                }
+            }
+        }
+
+        #[test]
+        fn solvers_find_planted_sums(
+            mut values in prop::collection::vec(0u32..=2020, 0..30),
+            pair in 0u32..=2020,
+        ) {
+            values.push(pair);
+            values.push(2020 - pair);
+            prop_assert!(day1_part1(&values).is_ok());
+        }
+    }
 }
@@ -1,65 +1,376 @@
+use std::{
+    collections::HashSet,
+    ops::{Range, RangeInclusive},
+};
+
 use aoc_runner_derive::{aoc, aoc_generator};
+use regex::Regex;
+
+use crate::{ParseError, SolveError};
 
-pub struct Password {
-    policy_range: (usize, usize),
-    policy_char: char,
-    password: String,
+pub struct Password<'a> {
+    pub policy_range: (usize, usize),
+    pub policy_char: char,
+    pub password: &'a str,
 }
 
-#[aoc_generator(day2)]
-pub fn input_generator(input: &str) -> Vec<Password> {
-    input
-        .lines()
-        .map(|line| {
-            let mut split = line.split(' ');
-            let mut policy_range = split.next().unwrap().split('-');
-            let policy_range = (
-                policy_range.next().unwrap().parse().unwrap(),
-                policy_range.next().unwrap().parse().unwrap(),
-            );
-            let policy_char = split.next().unwrap().chars().next().unwrap();
-            let password = split.next().unwrap().trim().to_owned();
-            Password {
-                policy_range,
-                policy_char,
-                password,
-            }
-        })
-        .collect()
+impl Password<'_> {
+    /// The sled rental place's reading of the policy.
+    pub fn count_policy(&self) -> CountRange {
+        CountRange {
+            range: self.policy_range.0..=self.policy_range.1,
+            c: self.policy_char,
+        }
+    }
+
+    /// The Official Toboggan Corporate reading of the policy.
+    pub fn position_policy(&self) -> PositionXor {
+        PositionXor {
+            positions: self.policy_range,
+            c: self.policy_char,
+        }
+    }
 }
 
-#[aoc(day2, part1)]
-pub fn day2_part1(input: &[Password]) -> usize {
-    input
-        .iter()
-        .filter(|&password| {
-            (password.policy_range.0..=password.policy_range.1).contains(
-                &password
-                    .password
-                    .chars()
-                    .filter(|&c| c == password.policy_char)
-                    .count(),
+pub trait PasswordPolicy {
+    /// Describes how `password` breaks this policy, or returns `None` if it doesn't.
+    fn violation(&self, password: &str) -> Result<Option<String>, SolveError>;
+
+    fn allows(&self, password: &str) -> Result<bool, SolveError> {
+        Ok(self.violation(password)?.is_none())
+    }
+}
+
+/// The password must contain `c` a number of times within `range`.
+pub struct CountRange {
+    pub range: RangeInclusive<usize>,
+    pub c: char,
+}
+
+impl PasswordPolicy for CountRange {
+    fn violation(&self, password: &str) -> Result<Option<String>, SolveError> {
+        // UTF-8 never reuses ASCII bytes inside other characters, so those can be counted directly.
+        let count = if self.c.is_ascii() {
+            password.bytes().filter(|&b| b == self.c as u8).count()
+        } else {
+            password.chars().filter(|&c| c == self.c).count()
+        };
+        Ok((!self.range.contains(&count)).then(|| {
+            format!(
+                "Contains {:?} {} times, expected {} to {}",
+                self.c,
+                count,
+                self.range.start(),
+                self.range.end()
             )
+        }))
+    }
+}
+
+/// Exactly one of the two (1-based) positions must hold `c`.
+pub struct PositionXor {
+    pub positions: (usize, usize),
+    pub c: char,
+}
+
+impl PasswordPolicy for PositionXor {
+    fn violation(&self, password: &str) -> Result<Option<String>, SolveError> {
+        let outside = |position: usize| {
+            SolveError::InvalidInput(format!(
+                "Position {} is outside password {:?}",
+                position, password
+            ))
+        };
+        let (first, second) = chars_at(password, self.positions.0, self.positions.1);
+        let first = first.ok_or_else(|| outside(self.positions.0))? == self.c;
+        let second = second.ok_or_else(|| outside(self.positions.1))? == self.c;
+        Ok(match (first, second) {
+            (true, false) | (false, true) => None,
+            (true, true) => Some(format!(
+                "Has {:?} at both positions {} and {}",
+                self.c, self.positions.0, self.positions.1
+            )),
+            (false, false) => Some(format!(
+                "Has {:?} at neither position {} nor {}",
+                self.c, self.positions.0, self.positions.1
+            )),
         })
-        .count()
+    }
 }
 
-#[aoc(day2, part2)]
-pub fn day2_part2(input: &[Password]) -> usize {
-    input
+/// The characters at two 1-based positions, indexing bytes directly if the password is ASCII and
+/// otherwise walking the characters once.
+fn chars_at(password: &str, first: usize, second: usize) -> (Option<char>, Option<char>) {
+    if password.is_ascii() {
+        let byte_at = |position: usize| {
+            position
+                .checked_sub(1)
+                .and_then(|i| password.as_bytes().get(i))
+                .map(|&b| char::from(b))
+        };
+        return (byte_at(first), byte_at(second));
+    }
+    let (mut first_char, mut second_char) = (None, None);
+    for (position, c) in (1..).zip(password.chars()) {
+        if position == first {
+            first_char = Some(c);
+        }
+        if position == second {
+            second_char = Some(c);
+        }
+        if position >= first && position >= second {
+            break;
+        }
+    }
+    (first_char, second_char)
+}
+
+pub struct MinDistinctChars(pub usize);
+
+impl PasswordPolicy for MinDistinctChars {
+    fn violation(&self, password: &str) -> Result<Option<String>, SolveError> {
+        let distinct = password.chars().collect::<HashSet<_>>().len();
+        Ok((distinct < self.0).then(|| {
+            format!(
+                "Has {} distinct characters, expected at least {}",
+                distinct, self.0
+            )
+        }))
+    }
+}
+
+pub struct ForbiddenSubstrings(pub Vec<String>);
+
+impl PasswordPolicy for ForbiddenSubstrings {
+    fn violation(&self, password: &str) -> Result<Option<String>, SolveError> {
+        let found: Vec<_> = self
+            .0
+            .iter()
+            .filter(|forbidden| password.contains(forbidden.as_str()))
+            .collect();
+        Ok((!found.is_empty()).then(|| format!("Contains forbidden {:?}", found)))
+    }
+}
+
+/// The password must match the regex somewhere, so anchor it to match the whole password.
+pub struct MatchesRegex(pub Regex);
+
+impl PasswordPolicy for MatchesRegex {
+    fn violation(&self, password: &str) -> Result<Option<String>, SolveError> {
+        Ok((!self.0.is_match(password)).then(|| format!("Doesn't match /{}/", self.0)))
+    }
+}
+
+/// Checks every password against its own policy (read as `line_policy` would) and the shared
+/// `policies`, returning how each one fails in the order the policies were given. A policy that
+/// can't be applied to a password, such as a position past its end, counts as a failure.
+pub fn audit<'a, P: PasswordPolicy>(
+    passwords: &[Password<'a>],
+    line_policy: impl Fn(&Password<'a>) -> P,
+    policies: &[&dyn PasswordPolicy],
+) -> Vec<Vec<String>> {
+    passwords
         .iter()
-        .filter(|&password| {
-            let first_char = password
-                .password
-                .chars()
-                .nth(password.policy_range.0 - 1)
-                .unwrap();
-            let second_char = password
-                .password
-                .chars()
-                .nth(password.policy_range.1 - 1)
-                .unwrap();
-            (first_char == password.policy_char) ^ (second_char == password.policy_char)
+        .map(|password| {
+            let line_policy = line_policy(password);
+            std::iter::once(&line_policy as &dyn PasswordPolicy)
+                .chain(policies.iter().copied())
+                .filter_map(|policy| match policy.violation(password.password) {
+                    Ok(violation) => violation,
+                    Err(
+                        SolveError::InvalidInput(reason)
+                        | SolveError::NoSolution(reason)
+                        | SolveError::Ambiguous(reason),
+                    ) => Some(reason),
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Parses each line lazily, borrowing the passwords from `input`.
+pub fn passwords(input: &str) -> impl Iterator<Item = Result<Password<'_>, ParseError>> {
+    input.lines().map(move |line| {
+        let err = |part: &str, message: &str| ParseError::at(2, input, part, message);
+        let end = &line[line.len()..];
+        let mut split = line.split(' ');
+        let policy_range = split.next().unwrap_or(end);
+        let (start, finish) = policy_range
+            .split_once('-')
+            .ok_or_else(|| err(policy_range, "Expected policy range like 1-3"))?;
+        let policy_range = (
+            start
+                .parse()
+                .map_err(|_| err(start, "Invalid policy range start"))?,
+            finish
+                .parse()
+                .map_err(|_| err(finish, "Invalid policy range end"))?,
+        );
+        let policy = split
+            .next()
+            .ok_or_else(|| err(end, "Missing policy character"))?;
+        let policy_char = match policy.strip_suffix(':').map(|c| {
+            let mut chars = c.chars();
+            (chars.next(), chars.next())
+        }) {
+            Some((Some(c), None)) => c,
+            _ => return Err(err(policy, "Expected policy character like a:")),
+        };
+        let password = split
+            .next()
+            .ok_or_else(|| err(end, "Missing password"))?
+            .trim();
+        Ok(Password {
+            policy_range,
+            policy_char,
+            password,
+        })
+    })
+}
+
+/// The puzzle input with every line parsed, handing out passwords borrowed from it.
+///
+/// aoc-runner keeps generator output alongside the input it was made from rather than borrowing
+/// from it, so this owns one copy of the input instead of a `String` per password.
+pub struct Passwords {
+    input: String,
+    /// Each password's policy and where the password is in `input`.
+    entries: Vec<((usize, usize), char, Range<usize>)>,
+}
+
+impl Passwords {
+    pub fn iter(&self) -> impl Iterator<Item = Password<'_>> + '_ {
+        self.entries
+            .iter()
+            .map(move |(policy_range, policy_char, password)| Password {
+                policy_range: *policy_range,
+                policy_char: *policy_char,
+                password: &self.input[password.clone()],
+            })
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[aoc_generator(day2)]
+pub fn input_generator(input: &str) -> Result<Passwords, ParseError> {
+    let entries = passwords(input)
+        .map(|password| {
+            let password = password?;
+            let start = password.password.as_ptr() as usize - input.as_ptr() as usize;
+            Ok((
+                password.policy_range,
+                password.policy_char,
+                start..start + password.password.len(),
+            ))
         })
-        .count()
+        .collect::<Result<_, ParseError>>()?;
+    Ok(Passwords {
+        input: input.to_owned(),
+        entries,
+    })
+}
+
+fn count_allowed<'a, P: PasswordPolicy>(
+    input: &'a Passwords,
+    policy: impl Fn(&Password<'a>) -> P,
+) -> Result<usize, SolveError> {
+    let mut count = 0;
+    for password in input.iter() {
+        if policy(&password).allows(password.password)? {
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+#[aoc(day2, part1)]
+pub fn day2_part1(input: &Passwords) -> Result<usize, SolveError> {
+    count_allowed(input, Password::count_policy)
+}
+
+#[aoc(day2, part2)]
+pub fn day2_part2(input: &Passwords) -> Result<usize, SolveError> {
+    count_allowed(input, Password::position_policy)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "1-3 a: abcde\n1-3 b: cdefg\n2-9 c: ccccccccc";
+
+    #[test]
+    fn example() {
+        let passwords = input_generator(EXAMPLE).unwrap();
+        assert_eq!(Ok(2), day2_part1(&passwords));
+        assert_eq!(Ok(1), day2_part2(&passwords));
+    }
+
+    #[test]
+    fn audit_reports_failures() {
+        let passwords: Vec<_> = passwords(EXAMPLE).collect::<Result<_, _>>().unwrap();
+        let forbidden = ForbiddenSubstrings(vec!["cccc".to_owned(), "de".to_owned()]);
+        let regex = MatchesRegex(Regex::new("^[a-z]{5,}$").unwrap());
+        let failures = audit(
+            &passwords,
+            Password::position_policy,
+            &[&MinDistinctChars(2), &forbidden, &regex],
+        );
+        assert_eq!(
+            vec![
+                vec![r#"Contains forbidden ["de"]"#.to_owned()],
+                vec![
+                    "Has 'b' at neither position 1 nor 3".to_owned(),
+                    r#"Contains forbidden ["de"]"#.to_owned(),
+                ],
+                vec![
+                    "Has 'c' at both positions 2 and 9".to_owned(),
+                    "Has 1 distinct characters, expected at least 2".to_owned(),
+                    r#"Contains forbidden ["cccc"]"#.to_owned(),
+                ],
+            ],
+            failures
+        );
+        let failures = audit(
+            &passwords,
+            |p| PositionXor {
+                positions: (1, 6),
+                ..p.position_policy()
+            },
+            &[],
+        );
+        assert_eq!(
+            vec![
+                vec![r#"Position 6 is outside password "abcde""#.to_owned()],
+                vec![r#"Position 6 is outside password "cdefg""#.to_owned()],
+                vec!["Has 'c' at both positions 1 and 6".to_owned()],
+            ],
+            failures
+        );
+    }
+
+    #[test]
+    fn non_ascii_passwords() {
+        let input = "1-3 é: éaé\n2-3 a: ééa\n1-2 a: aé\n1-4 a: aé";
+        let passwords = input_generator(input).unwrap();
+        assert_eq!(4, passwords.len());
+        assert_eq!("éaé", passwords.iter().next().unwrap().password);
+        assert_eq!((Some('é'), Some('é')), chars_at("éaé", 1, 3));
+        assert_eq!((Some('a'), None), chars_at("ééa", 3, 4));
+        assert_eq!(Ok(3), day2_part1(&passwords));
+        assert!(matches!(
+            day2_part2(&passwords),
+            Err(SolveError::InvalidInput(_))
+        ));
+        let first_three = input_generator(&input[..input.rfind('\n').unwrap()]).unwrap();
+        assert_eq!(Ok(2), day2_part2(&first_three));
+        assert!(input_generator("1-3 a abcde").is_err());
+    }
 }
@@ -1,50 +1,262 @@
+use std::{collections::HashSet, ops::RangeInclusive};
+
 use aoc_runner_derive::{aoc, aoc_generator};
 
+use crate::{
+    grid::{Cell, Grid, Pos},
+    ParseError, SolveError,
+};
+
 #[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq)]
 pub enum MapSquare {
     Open,
     Tree,
 }
 
+impl Cell for MapSquare {
+    fn from_char(c: char) -> Option<Self> {
+        match c {
+            '.' => Some(MapSquare::Open),
+            '#' => Some(MapSquare::Tree),
+            _ => None,
+        }
+    }
+
+    fn to_char(&self) -> char {
+        match self {
+            MapSquare::Open => '.',
+            MapSquare::Tree => '#',
+        }
+    }
+}
+
 #[aoc_generator(day3)]
-pub fn input_generator(input: &str) -> Box<[Box<[MapSquare]>]> {
-    input
-        .lines()
-        .map(|row| {
-            row.chars()
-                .map(|c| match c {
-                    '.' => MapSquare::Open,
-                    '#' => MapSquare::Tree,
-                    _ => panic!("Unexpected character"),
-                })
-                .collect()
-        })
-        .collect()
+pub fn input_generator(input: &str) -> Result<Grid<MapSquare>, ParseError> {
+    Grid::parse(3, input)
 }
 
-#[aoc(day3, part1)]
-pub fn day3_part1(input: &[Box<[MapSquare]>]) -> usize {
-    input
-        .iter()
+fn check_width(input: &Grid<MapSquare>) -> Result<(), SolveError> {
+    if input.width() == 0 && input.height() != 0 {
+        return Err(SolveError::InvalidInput("Map rows are empty".to_owned()));
+    }
+    Ok(())
+}
+
+fn check_slope(map: &Grid<MapSquare>, down: usize) -> Result<(), SolveError> {
+    check_width(map)?;
+    if down == 0 {
+        return Err(SolveError::InvalidInput(
+            "Slopes must go down at least one row".to_owned(),
+        ));
+    }
+    Ok(())
+}
+
+/// The rows and columns visited from the top left, without wrapping the columns onto the map.
+fn unwrapped_path(
+    map: &Grid<MapSquare>,
+    right: isize,
+    down: usize,
+) -> impl Iterator<Item = (usize, isize)> {
+    (0..map.height())
+        .step_by(down)
         .enumerate()
-        .filter(|(i, row)| row[(i * 3) % row.len()] == MapSquare::Tree)
-        .count()
+        .map(move |(i, row)| (row, i as isize * right))
 }
 
-#[aoc(day3, part2)]
-pub fn day3_part2(input: &[Box<[MapSquare]>]) -> usize {
-    const SLOPES: [(usize, usize); 5] = [(1, 1), (3, 1), (5, 1), (7, 1), (1, 2)];
+/// The squares visited going `right` (possibly negative) and `down` each step from the top left,
+/// with the map repeating to the left and right.
+pub fn path(
+    map: &Grid<MapSquare>,
+    right: isize,
+    down: usize,
+) -> Result<impl Iterator<Item = Pos> + '_, SolveError> {
+    check_slope(map, down)?;
+    let width = map.width() as isize;
+    // Moving `right` is the same as moving its remainder, which can't overflow along the path.
+    Ok(unwrapped_path(map, right.rem_euclid(width), down)
+        .map(move |(row, col)| (row, col.rem_euclid(width) as usize)))
+}
 
-    let trees = input
+/// Draws the map repeated sideways as far as the path goes, with every visited square marked `X`
+/// if it's a tree and `O` if it's open.
+pub fn render_path(map: &Grid<MapSquare>, right: isize, down: usize) -> Result<String, SolveError> {
+    check_slope(map, down)?;
+    let width = map.width() as isize;
+    let steps = (map.height().saturating_sub(1) / down) as isize;
+    if right.checked_mul(steps).is_none() {
+        return Err(SolveError::InvalidInput(format!(
+            "Going {} right each step is too far to draw",
+            right
+        )));
+    }
+    let visited: HashSet<_> = unwrapped_path(map, right, down).collect();
+    let first_tile = visited
         .iter()
-        .enumerate()
-        .fold([0usize; SLOPES.len()], |mut counts, (i, row)| {
-            for (count, &(right, down)) in counts.iter_mut().zip(SLOPES.iter()) {
-                if i % down == 0 && row[(i * right / down) % row.len()] == MapSquare::Tree {
-                    *count += 1;
-                }
+        .map(|&(_, col)| col.div_euclid(width))
+        .min()
+        .unwrap_or(0);
+    let last_tile = visited
+        .iter()
+        .map(|&(_, col)| col.div_euclid(width))
+        .max()
+        .unwrap_or(0);
+    let mut rendered = String::new();
+    for (row, squares) in map.rows().enumerate() {
+        for col in first_tile * width..(last_tile + 1) * width {
+            let square = squares[col.rem_euclid(width) as usize];
+            rendered.push(match (visited.contains(&(row, col)), square) {
+                (true, MapSquare::Tree) => 'X',
+                (true, MapSquare::Open) => 'O',
+                (false, square) => square.to_char(),
+            });
+        }
+        rendered.push('\n');
+    }
+    Ok(rendered)
+}
+
+pub fn trees_on_slope(
+    map: &Grid<MapSquare>,
+    right: isize,
+    down: usize,
+) -> Result<usize, SolveError> {
+    Ok(path(map, right, down)?
+        .filter(|&pos| map[pos] == MapSquare::Tree)
+        .count())
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Optimise {
+    Fewest,
+    Most,
+}
+
+/// The slope with the fewest or most trees out of every combination of `rights` and `downs`,
+/// preferring the first found (by `down`, then `right`) when several tie.
+pub fn best_slope(
+    map: &Grid<MapSquare>,
+    rights: RangeInclusive<isize>,
+    downs: RangeInclusive<usize>,
+    optimise: Optimise,
+) -> Result<((isize, usize), usize), SolveError> {
+    let mut best: Option<((isize, usize), usize)> = None;
+    for down in downs {
+        for right in rights.clone() {
+            let trees = trees_on_slope(map, right, down)?;
+            let better = match (best, optimise) {
+                (None, _) => true,
+                (Some((_, best)), Optimise::Fewest) => trees < best,
+                (Some((_, best)), Optimise::Most) => trees > best,
+            };
+            if better {
+                best = Some(((right, down), trees));
             }
-            counts
-        });
-    trees.iter().product()
+        }
+    }
+    best.ok_or_else(|| SolveError::NoSolution("No slopes to choose from".to_owned()))
+}
+
+#[aoc(day3, part1)]
+pub fn day3_part1(input: &Grid<MapSquare>) -> Result<usize, SolveError> {
+    trees_on_slope(input, 3, 1)
+}
+
+#[aoc(day3, part2)]
+pub fn day3_part2(input: &Grid<MapSquare>) -> Result<usize, SolveError> {
+    const SLOPES: [(isize, usize); 5] = [(1, 1), (3, 1), (5, 1), (7, 1), (1, 2)];
+    SLOPES
+        .iter()
+        .map(|&(right, down)| trees_on_slope(input, right, down))
+        .product()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+..##.......
+#...#...#..
+.#....#..#.
+..#.#...#.#
+.#...##..#.
+..#.##.....
+.#.#.#....#
+.#........#
+#.##...#...
+#...##....#
+.#..#...#.#";
+
+    #[test]
+    fn example() {
+        let map = input_generator(EXAMPLE).unwrap();
+        assert_eq!(Ok(7), day3_part1(&map));
+        assert_eq!(Ok(336), day3_part2(&map));
+        assert_eq!(Ok(2), trees_on_slope(&map, 1, 2));
+    }
+
+    #[test]
+    fn arbitrary_slopes() {
+        let map = input_generator(EXAMPLE).unwrap();
+        // Going left by 3 is the same as going right by 8 on a map 11 wide.
+        assert_eq!(trees_on_slope(&map, 8, 1), trees_on_slope(&map, -3, 1));
+        assert_eq!(trees_on_slope(&map, 1, 1), trees_on_slope(&map, 12, 1));
+        let visited: Vec<_> = path(&map, -1, 3).unwrap().collect();
+        assert_eq!(vec![(0, 0), (3, 10), (6, 9), (9, 8)], visited);
+        assert!(trees_on_slope(&map, 1, 0).is_err());
+        for right in [isize::MAX, isize::MIN] {
+            assert_eq!(
+                trees_on_slope(&map, right.rem_euclid(11), 1),
+                trees_on_slope(&map, right, 1)
+            );
+            assert!(render_path(&map, right, 1).is_err());
+        }
+
+        assert_eq!(
+            Ok(((2, 1), 1)),
+            best_slope(&map, -2..=2, 1..=2, Optimise::Fewest)
+        );
+        assert_eq!(
+            Ok(((3, 1), 7)),
+            best_slope(&map, -5..=5, 1..=1, Optimise::Most)
+        );
+        assert!(best_slope(&map, RangeInclusive::new(1, 0), 1..=2, Optimise::Most).is_err());
+    }
+
+    #[test]
+    fn render() {
+        let map = input_generator(EXAMPLE).unwrap();
+        let expected = "\
+O.##.........##.........##.
+#..O#...#..#...#...#..#...#
+.#....X..#..#....#..#..#...
+..#.#...#O#..#.#...#.#..#.#
+.#...##..#..X...##..#..#...
+..#.##.......#.X#.......#.#
+.#.#.#....#.#.#.#.O..#.#.#.
+.#........#.#........X.#...
+#.##...#...#.##...#...#.X#.
+#...##....##...##....##...#
+.#..#...#.#.#..#...#.#.#..#
+";
+        let rendered = render_path(&map, 3, 1).unwrap();
+        let cropped: String = rendered
+            .lines()
+            .map(|line| format!("{}\n", &line[..27]))
+            .collect();
+        assert_eq!(expected, cropped);
+        assert_eq!(33, rendered.lines().next().unwrap().len());
+        assert_eq!(
+            rendered.matches('X').count(),
+            trees_on_slope(&map, 3, 1).unwrap()
+        );
+
+        let left = render_path(&map, -1, 5).unwrap();
+        let left: Vec<_> = left.lines().collect();
+        assert_eq!(22, left[0].len());
+        assert_eq!(Some('O'), left[0].chars().nth(11));
+        assert_eq!(Some('O'), left[5].chars().nth(10));
+        assert_eq!(Some('O'), left[10].chars().nth(9));
+    }
 }
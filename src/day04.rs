@@ -1,8 +1,18 @@
-use std::str::FromStr;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    convert::TryFrom,
+    error::Error,
+    fmt, fs, io,
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
 
 use aoc_runner_derive::{aoc, aoc_generator};
 use regex::Regex;
 
+use crate::{normalise_line_endings, ParseError, SolveError};
+
 #[derive(Debug, Default, Clone)]
 pub struct Passport {
     birth_year: Option<String>,
@@ -13,10 +23,14 @@ pub struct Passport {
     eye_colour: Option<String>,
     passport_id: Option<String>,
     country_id: Option<String>,
+    /// Fields with unknown keys, only kept when parsing leniently.
+    pub extras: BTreeMap<String, String>,
+    /// Problems skipped over when parsing leniently.
+    pub warnings: Vec<ParseError>,
 }
 
-#[derive(Debug, Copy, Clone)]
-enum EyeColour {
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum EyeColour {
     Amber,
     Blue,
     Brown,
@@ -27,7 +41,7 @@ enum EyeColour {
 }
 
 impl FromStr for EyeColour {
-    type Err = ();
+    type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         Ok(match s {
@@ -38,43 +52,612 @@ impl FromStr for EyeColour {
             "grn" => EyeColour::Green,
             "hzl" => EyeColour::Hazel,
             "oth" => EyeColour::Other,
-            _ => return Err(()),
+            _ => return Err(format!("Unknown eye colour {:?}", s)),
         })
     }
 }
 
-#[derive(Debug)]
-enum Height {
+/// Parses a whole number written without a sign or leading zeros.
+fn canonical_number<T: FromStr>(digits: &str) -> Option<T> {
+    let canonical =
+        digits.bytes().all(|b| b.is_ascii_digit()) && (digits == "0" || !digits.starts_with('0'));
+    digits.parse().ok().filter(|_| canonical)
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Height {
     Centimeters(u8),
     Inches(u8),
 }
 
 impl FromStr for Height {
-    type Err = ();
+    type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Expected a height like 180cm or 70in, got {:?}", s);
         if let Some(cm) = s.strip_suffix("cm") {
-            cm.parse().map(Height::Centimeters).map_err(|_| ())
+            canonical_number(cm)
+                .map(Height::Centimeters)
+                .ok_or_else(invalid)
         } else if let Some(inches) = s.strip_suffix("in") {
-            inches.parse().map(Height::Inches).map_err(|_| ())
+            canonical_number(inches)
+                .map(Height::Inches)
+                .ok_or_else(invalid)
         } else {
-            Err(())
+            Err(invalid())
         }
     }
 }
 
-#[aoc_generator(day4)]
-pub fn input_generator(input: &str) -> Vec<Passport> {
+/// A colour written like `#1a2b3c`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct HexColour(pub [u8; 3]);
+
+impl FromStr for HexColour {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Expected a colour like #1a2b3c, got {:?}", s);
+        let digits = s.strip_prefix('#').ok_or_else(invalid)?;
+        if digits.len() != 6
+            || !digits
+                .bytes()
+                .all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+        {
+            return Err(invalid());
+        }
+        let channel = |i: usize| u8::from_str_radix(&digits[i..i + 2], 16).unwrap();
+        Ok(HexColour([channel(0), channel(2), channel(4)]))
+    }
+}
+
+/// Exactly nine digits, possibly with leading zeros.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PassportId(pub u32);
+
+impl FromStr for PassportId {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.len() != 9 || !s.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(format!("Expected a 9 digit ID, got {:?}", s));
+        }
+        Ok(PassportId(s.parse().unwrap()))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidatedPassport {
+    pub birth_year: u16,
+    pub issuer_year: u16,
+    pub expiration_year: u16,
+    pub height: Height,
+    pub hair_colour: HexColour,
+    pub eye_colour: EyeColour,
+    pub passport_id: PassportId,
+    pub country_id: Option<String>,
+}
+
+/// Why the field with the given key failed validation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldError {
+    pub field: String,
+    pub reason: String,
+}
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.field, self.reason)
+    }
+}
+
+impl TryFrom<&Passport> for ValidatedPassport {
+    type Error = Vec<FieldError>;
+
+    fn try_from(passport: &Passport) -> Result<Self, Self::Error> {
+        fn check<T>(
+            errors: &mut Vec<FieldError>,
+            field: &'static str,
+            value: &Option<String>,
+            validate: impl FnOnce(&str) -> Result<T, String>,
+        ) -> Option<T> {
+            let result = value
+                .as_deref()
+                .ok_or_else(|| "Missing".to_owned())
+                .and_then(validate);
+            result
+                .map_err(|reason| {
+                    errors.push(FieldError {
+                        field: field.to_owned(),
+                        reason,
+                    })
+                })
+                .ok()
+        }
+        let mut errors = Vec::new();
+        let year = |range: RangeInclusive<u16>| {
+            move |value: &str| {
+                let year = canonical_number(value)
+                    .filter(|_| value.len() == 4)
+                    .ok_or_else(|| format!("Expected a 4 digit year, got {:?}", value))?;
+                if range.contains(&year) {
+                    Ok(year)
+                } else {
+                    Err(format!(
+                        "{} is outside {} to {}",
+                        year,
+                        range.start(),
+                        range.end()
+                    ))
+                }
+            }
+        };
+        let birth_year = check(&mut errors, "byr", &passport.birth_year, year(1920..=2002));
+        let issuer_year = check(&mut errors, "iyr", &passport.issuer_year, year(2010..=2020));
+        let expiration_year = check(
+            &mut errors,
+            "eyr",
+            &passport.expiration_year,
+            year(2020..=2030),
+        );
+        let height = check(&mut errors, "hgt", &passport.height, |value| {
+            let height = value.parse()?;
+            let (value, range, unit) = match height {
+                Height::Centimeters(cm) => (cm, 150..=193, "cm"),
+                Height::Inches(inches) => (inches, 59..=76, "in"),
+            };
+            if range.contains(&value) {
+                Ok(height)
+            } else {
+                Err(format!(
+                    "{}{} is outside {}{} to {}{}",
+                    value,
+                    unit,
+                    range.start(),
+                    unit,
+                    range.end(),
+                    unit
+                ))
+            }
+        });
+        let hair_colour = check(&mut errors, "hcl", &passport.hair_colour, str::parse);
+        let eye_colour = check(&mut errors, "ecl", &passport.eye_colour, str::parse);
+        let passport_id = check(&mut errors, "pid", &passport.passport_id, str::parse);
+        match (
+            birth_year,
+            issuer_year,
+            expiration_year,
+            height,
+            hair_colour,
+            eye_colour,
+            passport_id,
+        ) {
+            (
+                Some(birth_year),
+                Some(issuer_year),
+                Some(expiration_year),
+                Some(height),
+                Some(hair_colour),
+                Some(eye_colour),
+                Some(passport_id),
+            ) => Ok(ValidatedPassport {
+                birth_year,
+                issuer_year,
+                expiration_year,
+                height,
+                hair_colour,
+                eye_colour,
+                passport_id,
+                country_id: passport.country_id.clone(),
+            }),
+            _ => Err(errors),
+        }
+    }
+}
+
+/// The keys of the known fields, in the order they're written out.
+pub const FIELDS: [&str; 8] = ["byr", "iyr", "eyr", "hgt", "hcl", "ecl", "pid", "cid"];
+
+impl Passport {
+    /// The value of the field with the given key, including unknown fields kept in `extras`.
+    pub fn get(&self, field: &str) -> Option<&str> {
+        let value = match field {
+            "byr" => &self.birth_year,
+            "iyr" => &self.issuer_year,
+            "eyr" => &self.expiration_year,
+            "hgt" => &self.height,
+            "hcl" => &self.hair_colour,
+            "ecl" => &self.eye_colour,
+            "pid" => &self.passport_id,
+            "cid" => &self.country_id,
+            field => return self.extras.get(field).map(String::as_str),
+        };
+        value.as_deref()
+    }
+
+    /// Sets or, given `None`, removes the field with the given key.
+    pub fn set(&mut self, field: &str, value: Option<String>) {
+        let slot = match field {
+            "byr" => &mut self.birth_year,
+            "iyr" => &mut self.issuer_year,
+            "eyr" => &mut self.expiration_year,
+            "hgt" => &mut self.height,
+            "hcl" => &mut self.hair_colour,
+            "ecl" => &mut self.eye_colour,
+            "pid" => &mut self.passport_id,
+            "cid" => &mut self.country_id,
+            field => {
+                match value {
+                    Some(value) => self.extras.insert(field.to_owned(), value),
+                    None => self.extras.remove(field),
+                };
+                return;
+            }
+        };
+        *slot = value;
+    }
+
+    /// Every field present, the known ones in `FIELDS` order followed by the extras by key.
+    pub fn fields(&self) -> impl Iterator<Item = (&str, &str)> {
+        FIELDS
+            .iter()
+            .filter_map(move |&field| Some((field, self.get(field)?)))
+            .chain(
+                self.extras
+                    .iter()
+                    .map(|(field, value)| (field.as_str(), value.as_str())),
+            )
+    }
+}
+
+/// Writes the passport's fields on one line, in the format `parse_passports` reads.
+impl fmt::Display for Passport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, (field, value)) in self.fields().enumerate() {
+            if i > 0 {
+                f.write_str(" ")?;
+            }
+            write!(f, "{}:{}", field, value)?;
+        }
+        Ok(())
+    }
+}
+
+/// The puzzle's rules, in the format read by `RuleSet::from_str`. These accept exactly the
+/// passports `ValidatedPassport` does.
+pub const DEFAULT_RULES: &str = "\
+byr required range 1920 2002
+iyr required range 2010 2020
+eyr required range 2020 2030
+hgt required units cm=150-193 in=59-76
+hcl required regex ^#[0-9a-f]{6}$
+ecl required one-of amb blu brn gry grn hzl oth
+pid required regex ^[0-9]{9}$
+cid optional
+";
+
+#[derive(Debug, Clone)]
+pub enum Constraint {
+    Any,
+    /// A whole number within the range, written without a sign or leading zeros.
+    Range(RangeInclusive<u64>),
+    /// A number as for `Range` directly followed by one of the units, within that unit's range.
+    Units(Vec<(String, RangeInclusive<u64>)>),
+    Regex(Regex),
+    OneOf(Vec<String>),
+}
+
+impl Constraint {
+    fn check(&self, value: &str) -> Result<(), String> {
+        let number = canonical_number::<u64>;
+        match self {
+            Constraint::Any => Ok(()),
+            Constraint::Range(range) => match number(value) {
+                Some(n) if range.contains(&n) => Ok(()),
+                Some(n) => Err(format!(
+                    "{} is outside {} to {}",
+                    n,
+                    range.start(),
+                    range.end()
+                )),
+                None => Err(format!("Expected a number, got {:?}", value)),
+            },
+            Constraint::Units(units) => {
+                for (unit, range) in units {
+                    if let Some(n) = value.strip_suffix(unit.as_str()).and_then(number) {
+                        if range.contains(&n) {
+                            return Ok(());
+                        }
+                        return Err(format!(
+                            "{}{} is outside {}{} to {}{}",
+                            n,
+                            unit,
+                            range.start(),
+                            unit,
+                            range.end(),
+                            unit
+                        ));
+                    }
+                }
+                let units: Vec<_> = units.iter().map(|(unit, _)| unit.as_str()).collect();
+                Err(format!(
+                    "Expected a number in {}, got {:?}",
+                    units.join(" or "),
+                    value
+                ))
+            }
+            Constraint::Regex(regex) if regex.is_match(value) => Ok(()),
+            Constraint::Regex(regex) => Err(format!("{:?} doesn't match /{}/", value, regex)),
+            Constraint::OneOf(options) if options.iter().any(|option| option == value) => Ok(()),
+            Constraint::OneOf(options) => {
+                Err(format!("{:?} isn't one of {}", value, options.join(", ")))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FieldRule {
+    pub field: String,
+    pub required: bool,
+    pub constraint: Constraint,
+}
+
+/// Validation rules for passports, read from lines like `byr required range 1920 2002`.
+///
+/// Each line names a field, whether it's `required` or `optional`, and then optionally one of
+/// these constraints on its value:
+/// - `any`
+/// - `range MIN MAX`
+/// - `units UNIT=MIN-MAX...`, such as `units cm=150-193 in=59-76`
+/// - `regex PATTERN`, where the pattern runs to the end of the line
+/// - `one-of OPTION...`
+///
+/// Blank lines and lines starting with `#` are ignored.
+#[derive(Debug, Clone)]
+pub struct RuleSet {
+    pub rules: Vec<FieldRule>,
+}
+
+impl RuleSet {
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, RuleError> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path).map_err(|error| RuleError::Io {
+            path: path.to_owned(),
+            error,
+        })?;
+        text.parse().map_err(|e| match e {
+            RuleError::Parse { error, .. } => RuleError::Parse {
+                path: Some(path.to_owned()),
+                error,
+            },
+            e => e,
+        })
+    }
+
+    /// The required fields the passport doesn't have.
+    pub fn missing<'a>(&'a self, passport: &Passport) -> Vec<&'a str> {
+        self.rules
+            .iter()
+            .filter(|rule| rule.required && passport.get(&rule.field).is_none())
+            .map(|rule| rule.field.as_str())
+            .collect()
+    }
+
+    /// Every missing required field and every field breaking its constraint.
+    pub fn check(&self, passport: &Passport) -> Vec<FieldError> {
+        self.rules
+            .iter()
+            .filter_map(|rule| {
+                let reason = match passport.get(&rule.field) {
+                    None if rule.required => "Missing".to_owned(),
+                    None => return None,
+                    Some(value) => rule.constraint.check(value).err()?,
+                };
+                Some(FieldError {
+                    field: rule.field.clone(),
+                    reason,
+                })
+            })
+            .collect()
+    }
+}
+
+impl Default for RuleSet {
+    fn default() -> Self {
+        DEFAULT_RULES.parse().unwrap()
+    }
+}
+
+impl FromStr for RuleSet {
+    type Err = RuleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rules = s
+            .lines()
+            .filter(|line| {
+                let line = line.trim();
+                !line.is_empty() && !line.starts_with('#')
+            })
+            .map(|line| parse_rule(s, line))
+            .collect::<Result<_, _>>()
+            .map_err(|error| RuleError::Parse { path: None, error })?;
+        Ok(RuleSet { rules })
+    }
+}
+
+/// A rule file that couldn't be read or parsed.
+#[derive(Debug)]
+pub enum RuleError {
+    Io {
+        path: PathBuf,
+        error: io::Error,
+    },
+    /// `path` is `None` for rules that didn't come from a file.
+    Parse {
+        path: Option<PathBuf>,
+        error: ParseError,
+    },
+}
+
+impl fmt::Display for RuleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuleError::Io { path, error } => {
+                write!(f, "Couldn't read rules file {}: {}", path.display(), error)
+            }
+            RuleError::Parse { path, error } => {
+                write!(f, "Rules file")?;
+                if let Some(path) = path {
+                    write!(f, " {}", path.display())?;
+                }
+                write!(
+                    f,
+                    ", line {}, column {}: {}",
+                    error.line, error.column, error.message
+                )
+            }
+        }
+    }
+}
+
+impl Error for RuleError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            RuleError::Io { error, .. } => Some(error),
+            RuleError::Parse { .. } => None,
+        }
+    }
+}
+
+/// Parses one line of a rule file, which must be a slice of `input`.
+fn parse_rule(input: &str, line: &str) -> Result<FieldRule, ParseError> {
+    let err = |part: &str, message: String| ParseError::at(4, input, part, message);
+    let end = &line[line.len()..];
+    let parse_range = |min: &str, max: &str| -> Result<RangeInclusive<u64>, ParseError> {
+        let bound = |bound: &str| {
+            bound
+                .parse()
+                .map_err(|_| err(bound, format!("Invalid bound {:?}", bound)))
+        };
+        Ok(bound(min)?..=bound(max)?)
+    };
+
+    let mut words = line.split_whitespace();
+    let field = words
+        .next()
+        .ok_or_else(|| err(line, "Expected a field name".to_owned()))?;
+    let required = match words.next() {
+        Some("required") => true,
+        Some("optional") => false,
+        Some(other) => {
+            return Err(err(
+                other,
+                format!("Expected required or optional, got {:?}", other),
+            ))
+        }
+        None => return Err(err(end, "Expected required or optional".to_owned())),
+    };
+    let keyword = match words.next() {
+        Some(keyword) => keyword,
+        None => {
+            return Ok(FieldRule {
+                field: field.to_owned(),
+                required,
+                constraint: Constraint::Any,
+            })
+        }
+    };
+    let args: Vec<_> = words.collect();
+    let constraint = match keyword {
+        "any" if args.is_empty() => Constraint::Any,
+        "range" => match args[..] {
+            [min, max] => Constraint::Range(parse_range(min, max)?),
+            _ => return Err(err(keyword, "Expected range MIN MAX".to_owned())),
+        },
+        "units" if !args.is_empty() => Constraint::Units(
+            args.iter()
+                .map(|&arg| {
+                    let (unit, range) = arg.split_once('=').ok_or_else(|| {
+                        err(
+                            arg,
+                            format!("Expected a unit like cm=150-193, got {:?}", arg),
+                        )
+                    })?;
+                    let (min, max) = range.split_once('-').ok_or_else(|| {
+                        err(
+                            range,
+                            format!("Expected a range like 150-193, got {:?}", range),
+                        )
+                    })?;
+                    Ok((unit.to_owned(), parse_range(min, max)?))
+                })
+                .collect::<Result<_, ParseError>>()?,
+        ),
+        "regex" => {
+            let after = keyword.as_ptr() as usize - line.as_ptr() as usize + keyword.len();
+            let pattern = line[after..].trim();
+            if pattern.is_empty() {
+                return Err(err(end, "Expected a regex pattern".to_owned()));
+            }
+            Constraint::Regex(
+                Regex::new(pattern).map_err(|e| err(pattern, format!("Invalid regex: {}", e)))?,
+            )
+        }
+        "one-of" if !args.is_empty() => {
+            Constraint::OneOf(args.iter().map(|&arg| arg.to_owned()).collect())
+        }
+        "any" => return Err(err(args[0], "any takes no arguments".to_owned())),
+        "units" | "one-of" => return Err(err(end, format!("Expected arguments for {}", keyword))),
+        other => return Err(err(other, format!("Invalid constraint {:?}", other))),
+    };
+    Ok(FieldRule {
+        field: field.to_owned(),
+        required,
+        constraint,
+    })
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Mode {
+    /// Rejects the whole batch at the first malformed, duplicate or unknown field.
+    Strict,
+    /// Keeps unknown fields in `extras`, and the first of any duplicates, skipping malformed
+    /// fields. Everything skipped is recorded in the passport's `warnings`.
+    Lenient,
+}
+
+pub fn parse_passports(input: &str, mode: Mode) -> Result<Vec<Passport>, ParseError> {
+    let input = normalise_line_endings(input);
+    let input = &*input;
     input
         .split("\n\n")
         .map(|passport_data| {
             let mut passport = Passport::default();
+            let mut warnings = Vec::new();
             for item in passport_data.split_whitespace() {
-                let mut parts = item.split(':');
-                let field_name = parts.next().unwrap();
-                let field_value = parts.next().unwrap();
-                assert!(parts.next().is_none());
-                let old = match field_name {
+                let mut problem = |message: String| {
+                    let err = ParseError::at(4, input, item, message);
+                    match mode {
+                        Mode::Strict => Err(err),
+                        Mode::Lenient => {
+                            warnings.push(err);
+                            Ok(())
+                        }
+                    }
+                };
+                let (field_name, field_value) = match item.split_once(':') {
+                    None => {
+                        problem(format!("Field {:?} has no value", item))?;
+                        continue;
+                    }
+                    Some((_, value)) if value.contains(':') => {
+                        problem(format!("Field {:?} contains more than one ':'", item))?;
+                        continue;
+                    }
+                    Some(field) => field,
+                };
+                let field = match field_name {
                     "byr" => &mut passport.birth_year,
                     "iyr" => &mut passport.issuer_year,
                     "eyr" => &mut passport.expiration_year,
@@ -83,82 +666,561 @@ pub fn input_generator(input: &str) -> Vec<Passport> {
                     "ecl" => &mut passport.eye_colour,
                     "pid" => &mut passport.passport_id,
                     "cid" => &mut passport.country_id,
-                    field => panic!("Unknown field {}", field),
+                    field if mode == Mode::Strict => {
+                        return Err(ParseError::at(
+                            4,
+                            input,
+                            item,
+                            format!("Unknown field {}", field),
+                        ))
+                    }
+                    field => {
+                        if passport.extras.contains_key(field) {
+                            problem(format!("Duplicate field {}", field))?;
+                        } else {
+                            passport
+                                .extras
+                                .insert(field.to_owned(), field_value.to_owned());
+                        }
+                        continue;
+                    }
+                };
+                if field.is_some() {
+                    problem(format!("Duplicate field {}", field_name))?;
+                } else {
+                    *field = Some(field_value.to_owned());
                 }
-                .replace(field_value.to_owned());
-                assert!(old.is_none());
             }
-            passport
+            passport.warnings = warnings;
+            Ok(passport)
         })
         .collect()
 }
 
-#[aoc(day4, part1)]
-pub fn day4_part1(input: &[Passport]) -> usize {
-    input
+#[aoc_generator(day4)]
+pub fn input_generator(input: &str) -> Result<Vec<Passport>, ParseError> {
+    parse_passports(input, Mode::Strict)
+}
+
+/// Writes the passports in the format `parse_passports` reads, separated by blank lines.
+pub fn write_passports(passports: &[Passport]) -> String {
+    let mut output = String::new();
+    for passport in passports {
+        if !output.is_empty() {
+            output.push('\n');
+        }
+        output.push_str(&passport.to_string());
+        output.push('\n');
+    }
+    output
+}
+
+/// Writes the passports as CSV, with a column for each of `FIELDS` followed by one for each
+/// extra field any passport has.
+///
+/// Missing fields are left empty, and fields present with an empty value are written as `""`.
+pub fn to_csv(passports: &[Passport]) -> String {
+    let mut columns: Vec<&str> = FIELDS.to_vec();
+    let extras: BTreeSet<&str> = passports
         .iter()
-        .filter(|&pp| {
-            pp.birth_year.is_some()
-                && pp.issuer_year.is_some()
-                && pp.expiration_year.is_some()
-                && pp.height.is_some()
-                && pp.hair_colour.is_some()
-                && pp.eye_colour.is_some()
-                && pp.passport_id.is_some()
+        .flat_map(|passport| passport.extras.keys().map(String::as_str))
+        .collect();
+    columns.extend(extras);
+
+    let cell = |value: &str| {
+        if value.is_empty() || value.contains(&[',', '"'][..]) {
+            format!("\"{}\"", value.replace('"', "\"\""))
+        } else {
+            value.to_owned()
+        }
+    };
+    let mut output = String::new();
+    let mut write_row = |cells: Vec<String>| {
+        output.push_str(&cells.join(","));
+        output.push('\n');
+    };
+    write_row(columns.iter().map(|column| cell(column)).collect());
+    for passport in passports {
+        write_row(
+            columns
+                .iter()
+                .map(|&column| passport.get(column).map(cell).unwrap_or_default())
+                .collect(),
+        );
+    }
+    output
+}
+
+/// A CSV cell's value, with the slice of the line it starts at. Empty unquoted cells are `None`.
+type CsvCell<'a> = (&'a str, Option<String>);
+
+fn csv_cells(line: &str) -> Result<Vec<CsvCell<'_>>, (&str, String)> {
+    let mut cells = Vec::new();
+    let mut rest = line;
+    loop {
+        let start = rest;
+        let value = if let Some(quoted) = rest.strip_prefix('"') {
+            let mut value = String::new();
+            let mut chars = quoted.char_indices();
+            loop {
+                match chars.next() {
+                    None => return Err((start, "Unterminated quoted cell".to_owned())),
+                    Some((i, '"')) if quoted[i + 1..].starts_with('"') => {
+                        value.push('"');
+                        chars.next();
+                    }
+                    Some((i, '"')) => {
+                        rest = &quoted[i + 1..];
+                        break;
+                    }
+                    Some((_, c)) => value.push(c),
+                }
+            }
+            Some(value)
+        } else {
+            let (cell, after) = rest.split_at(rest.find(',').unwrap_or(rest.len()));
+            if cell.contains('"') {
+                return Err((start, format!("Unquoted cell {:?} contains '\"'", cell)));
+            }
+            rest = after;
+            Some(cell.to_owned()).filter(|cell| !cell.is_empty())
+        };
+        cells.push((start, value));
+        match rest.strip_prefix(',') {
+            Some(after) => rest = after,
+            None if rest.is_empty() => return Ok(cells),
+            None => return Err((rest, "Expected ',' after quoted cell".to_owned())),
+        }
+    }
+}
+
+/// Reads passports from CSV as written by `to_csv`. Columns other than `FIELDS` become extras.
+pub fn from_csv(input: &str) -> Result<Vec<Passport>, ParseError> {
+    let error = |(part, message): (&str, String)| ParseError::at(4, input, part, message);
+    let mut lines = input.lines().filter(|line| !line.is_empty());
+    let header = match lines.next() {
+        Some(header) => header,
+        None => return Ok(Vec::new()),
+    };
+    let mut columns: Vec<String> = Vec::new();
+    for (part, column) in csv_cells(header).map_err(error)? {
+        let column = column.unwrap_or_default();
+        if columns.contains(&column) {
+            return Err(error((part, format!("Duplicate column {}", column))));
+        }
+        if column.contains(|c: char| c == ':' || c.is_whitespace()) {
+            return Err(error((part, format!("Invalid field name {:?}", column))));
+        }
+        columns.push(column);
+    }
+    lines
+        .map(|line| {
+            let cells = csv_cells(line).map_err(error)?;
+            if cells.len() != columns.len() {
+                return Err(error((
+                    line,
+                    format!("Expected {} cells, got {}", columns.len(), cells.len()),
+                )));
+            }
+            let mut passport = Passport::default();
+            for (column, (part, value)) in columns.iter().zip(cells) {
+                if let Some(value) = &value {
+                    if value.contains(|c: char| c == ':' || c.is_whitespace()) {
+                        return Err(error((part, format!("Invalid value {:?}", value))));
+                    }
+                }
+                passport.set(column, value);
+            }
+            Ok(passport)
         })
-        .count()
+        .collect()
+}
+
+#[aoc(day4, part1)]
+pub fn day4_part1(input: &[Passport]) -> Result<usize, SolveError> {
+    let rules = RuleSet::default();
+    Ok(input
+        .iter()
+        .filter(|&passport| rules.missing(passport).is_empty())
+        .count())
 }
 
 #[aoc(day4, part2)]
-pub fn day4_part2(input: &[Passport]) -> usize {
-    let hair_colour_regex = Regex::new("^#[0-9a-f]{6}$").unwrap();
-    let passport_regex = Regex::new("^[0-9]{9}$").unwrap();
-    input
+pub fn day4_part2(input: &[Passport]) -> Result<usize, SolveError> {
+    Ok(input
         .iter()
-        .filter(|&pp| {
-            let byr_valid = pp
-                .birth_year
-                .as_ref()
-                .and_then(|byr| byr.parse().ok())
-                .map(|byr| (1920u16..=2002).contains(&byr))
-                .unwrap_or(false);
-            let iyr_valid = pp
-                .issuer_year
-                .as_ref()
-                .and_then(|iyr| iyr.parse().ok())
-                .map(|iyr| (2010u16..=2020).contains(&iyr))
-                .unwrap_or(false);
-            let eyr_valid = pp
-                .expiration_year
-                .as_ref()
-                .and_then(|eyr| eyr.parse().ok())
-                .map(|eyr| (2020u16..=2030).contains(&eyr))
-                .unwrap_or(false);
-            let hgt_valid = pp
-                .height
-                .as_ref()
-                .and_then(|hgt| hgt.parse::<Height>().ok())
-                .map(|hgt| match hgt {
-                    Height::Centimeters(cm) => (150..=193).contains(&cm),
-                    Height::Inches(inches) => (59..=76).contains(&inches),
-                })
-                .unwrap_or(false);
-            let hcl_valid = pp
-                .hair_colour
-                .as_ref()
-                .map(|hcl| hair_colour_regex.is_match(hcl))
-                .unwrap_or(false);
-            let ecl_valid = pp
-                .eye_colour
-                .as_ref()
-                .map(|ecl| ecl.parse::<EyeColour>().ok().is_some())
-                .unwrap_or(false);
-            let pid_valid = pp
-                .passport_id
-                .as_ref()
-                .map(|pid| passport_regex.is_match(pid))
-                .unwrap_or(false);
-            byr_valid && iyr_valid && eyr_valid && hgt_valid && hcl_valid && ecl_valid && pid_valid
-        })
-        .count()
+        .filter(|&passport| ValidatedPassport::try_from(passport).is_ok())
+        .count())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INVALID: &str = "\
+eyr:1972 cid:100
+hcl:#18171d ecl:amb hgt:170 pid:186cm iyr:2018 byr:1926
+
+iyr:2019
+hcl:#602927 eyr:1967 hgt:170cm
+ecl:grn pid:012533040 byr:1946
+
+hcl:dab227 iyr:2012
+ecl:brn hgt:182cm pid:021572410 eyr:2020 byr:1992 cid:277
+
+hgt:59cm ecl:zzz
+eyr:2038 hcl:74454a iyr:2023
+pid:3556412378 byr:2007";
+
+    const VALID: &str = "\
+pid:087499704 hgt:74in ecl:grn iyr:2012 eyr:2030 byr:1980
+hcl:#623a2f
+
+eyr:2029 ecl:blu cid:129 byr:1989
+iyr:2014 pid:896056539 hcl:#a97842 hgt:165cm
+
+hcl:#888785
+hgt:164cm byr:2001 iyr:2015 cid:88
+pid:545766238 ecl:hzl
+eyr:2022
+
+iyr:2010 hgt:158cm hcl:#b6652a ecl:blu byr:1944 eyr:2021 pid:093154719";
+
+    #[test]
+    fn example() {
+        assert_eq!(Ok(0), day4_part2(&input_generator(INVALID).unwrap()));
+        assert_eq!(Ok(4), day4_part2(&input_generator(VALID).unwrap()));
+    }
+
+    #[test]
+    fn crlf_input() {
+        let input = INVALID.replace('\n', "\r\n");
+        assert_eq!(Ok(4), day4_part1(&input_generator(&input).unwrap()));
+        assert_eq!(Ok(0), day4_part2(&input_generator(&input).unwrap()));
+        let input = VALID.replace('\n', "\r\n");
+        assert_eq!(Ok(4), day4_part2(&input_generator(&input).unwrap()));
+    }
+
+    #[test]
+    fn field_errors() {
+        let passports = input_generator(INVALID).unwrap();
+        let fields = |passport: &Passport| -> Vec<String> {
+            ValidatedPassport::try_from(passport)
+                .unwrap_err()
+                .iter()
+                .map(|e| e.field.clone())
+                .collect()
+        };
+        assert_eq!(vec!["eyr", "hgt", "pid"], fields(&passports[0]));
+        assert_eq!(vec!["eyr"], fields(&passports[1]));
+        assert_eq!(vec!["hcl"], fields(&passports[2]));
+        assert_eq!(
+            vec!["byr", "iyr", "eyr", "hgt", "hcl", "ecl", "pid"],
+            fields(&passports[3])
+        );
+        let errors = ValidatedPassport::try_from(&passports[3]).unwrap_err();
+        assert_eq!("byr: 2007 is outside 1920 to 2002", errors[0].to_string());
+        assert_eq!("hgt: 59cm is outside 150cm to 193cm", errors[3].to_string());
+        let errors = ValidatedPassport::try_from(&Passport::default()).unwrap_err();
+        assert_eq!(7, errors.len());
+        assert!(errors.iter().all(|e| e.reason == "Missing"));
+    }
+
+    #[test]
+    fn validated_fields() {
+        let passports = input_generator(VALID).unwrap();
+        assert_eq!(
+            ValidatedPassport {
+                birth_year: 1980,
+                issuer_year: 2012,
+                expiration_year: 2030,
+                height: Height::Inches(74),
+                hair_colour: HexColour([0x62, 0x3a, 0x2f]),
+                eye_colour: EyeColour::Green,
+                passport_id: PassportId(87499704),
+                country_id: None,
+            },
+            ValidatedPassport::try_from(&passports[0]).unwrap()
+        );
+        assert_eq!(
+            Some("88".to_owned()),
+            ValidatedPassport::try_from(&passports[2])
+                .unwrap()
+                .country_id
+        );
+    }
+
+    #[test]
+    fn lenient_parsing() {
+        let input = "byr:1980 xyz:1 hgt:74in\nbyr:1990 oops xyz:2 a:b:c\n\nhcl:#623a2f";
+        let err = parse_passports(input, Mode::Strict).unwrap_err();
+        assert_eq!(
+            (1, 10, "Unknown field xyz"),
+            (err.line, err.column, &err.message[..])
+        );
+
+        let passports = parse_passports(input, Mode::Lenient).unwrap();
+        assert_eq!(2, passports.len());
+        let passport = &passports[0];
+        assert_eq!(Some("1980"), passport.birth_year.as_deref());
+        assert_eq!(Some("74in"), passport.height.as_deref());
+        let extras: Vec<_> = passport.extras.iter().collect();
+        assert_eq!(vec![(&"xyz".to_owned(), &"1".to_owned())], extras);
+        let warnings: Vec<_> = passport
+            .warnings
+            .iter()
+            .map(|w| (w.line, w.column, &w.message[..]))
+            .collect();
+        assert_eq!(
+            vec![
+                (2, 1, "Duplicate field byr"),
+                (2, 10, "Field \"oops\" has no value"),
+                (2, 15, "Duplicate field xyz"),
+                (2, 21, "Field \"a:b:c\" contains more than one ':'"),
+            ],
+            warnings
+        );
+        assert!(passports[1].warnings.is_empty());
+    }
+
+    #[test]
+    fn rules_match_validated_passport() {
+        let rules = RuleSet::default();
+        let fields = |errors: Vec<FieldError>| -> Vec<String> {
+            errors.into_iter().map(|e| e.field).collect()
+        };
+        for passport in input_generator(INVALID)
+            .unwrap()
+            .iter()
+            .chain(&input_generator(VALID).unwrap())
+        {
+            assert_eq!(
+                fields(
+                    ValidatedPassport::try_from(passport)
+                        .err()
+                        .unwrap_or_default()
+                ),
+                fields(rules.check(passport))
+            );
+        }
+    }
+
+    #[test]
+    fn custom_rules() {
+        let rules: RuleSet = "\
+# Stricter rules
+byr required range 1950 2002
+hgt optional units cm=150-193
+hcl required regex ^#[0-9a-f]{3}( [0-9a-f]{3})?$
+ecl required one-of blu grn
+xyz required any
+"
+        .parse()
+        .unwrap();
+        let passports = parse_passports(
+            "byr:1944 hgt:74in hcl:#fff ecl:amb\n\nbyr:1980 hcl:#fff aaa ecl:blu xyz:1",
+            Mode::Lenient,
+        )
+        .unwrap();
+        let errors: Vec<_> = rules
+            .check(&passports[0])
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            vec![
+                "byr: 1944 is outside 1950 to 2002",
+                "hgt: Expected a number in cm, got \"74in\"",
+                "ecl: \"amb\" isn't one of blu, grn",
+                "xyz: Missing",
+            ],
+            errors
+        );
+        assert_eq!(vec!["xyz"], rules.missing(&passports[0]));
+        // The lenient parser drops "aaa", leaving "#fff" to match.
+        assert!(rules.check(&passports[1]).is_empty());
+
+        let error = |rules: &str| match rules.parse::<RuleSet>().unwrap_err() {
+            RuleError::Parse { path: None, error } => (error.line, error.column, error.message),
+            e => panic!("Unexpected error: {}", e),
+        };
+        assert_eq!(
+            (
+                2,
+                5,
+                "Expected required or optional, got \"sometimes\"".to_owned()
+            ),
+            error("byr required\nhgt sometimes")
+        );
+        assert_eq!(
+            (1, 14, "Expected range MIN MAX".to_owned()),
+            error("byr required range 1")
+        );
+        assert_eq!(
+            (1, 21, "Expected a regex pattern".to_owned()),
+            error("hcl required regex  ")
+        );
+        assert_eq!(1, error("hcl required regex (").0);
+        assert_eq!(
+            (1, 20, "Expected arguments for one-of".to_owned()),
+            error("ecl required one-of")
+        );
+        assert_eq!(
+            (1, 18, "any takes no arguments".to_owned()),
+            error("ecl required any x")
+        );
+        assert_eq!(
+            "Rules file, line 1, column 18: any takes no arguments",
+            "ecl required any x"
+                .parse::<RuleSet>()
+                .unwrap_err()
+                .to_string()
+        );
+
+        let path = std::env::temp_dir().join(format!("day04-rules-{}.txt", std::process::id()));
+        fs::write(&path, "# Eye colour\necl required any x\n").unwrap();
+        let message = RuleSet::from_file(&path).unwrap_err().to_string();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(
+            format!(
+                "Rules file {}, line 2, column 18: any takes no arguments",
+                path.display()
+            ),
+            message
+        );
+        assert!(matches!(
+            RuleSet::from_file(&path),
+            Err(RuleError::Io { .. })
+        ));
+
+        // Extra whitespace between the words doesn't end up in the pattern.
+        let rules: RuleSet = "hcl  required\tregex   ^#[0-9a-f]{6}$".parse().unwrap();
+        let passports = input_generator("hcl:#123abc\n\nhcl:#123abz").unwrap();
+        assert!(rules.check(&passports[0]).is_empty());
+        assert_eq!(1, rules.check(&passports[1]).len());
+    }
+
+    fn written(passports: &[Passport]) -> Vec<String> {
+        passports.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn write_round_trip() {
+        let passports = input_generator(INVALID).unwrap();
+        let output = write_passports(&passports);
+        assert!(output.starts_with(
+            "byr:1926 iyr:2018 eyr:1972 hgt:170 hcl:#18171d ecl:amb pid:186cm cid:100\n\n"
+        ));
+        let reparsed = input_generator(&output).unwrap();
+        assert_eq!(written(&passports), written(&reparsed));
+        assert_eq!(output, write_passports(&reparsed));
+
+        let mut passports = parse_passports("zzz:1 pid:123 aaa:2\n\nhgt:", Mode::Lenient).unwrap();
+        passports[0].set("pid", None);
+        passports[0].set("aaa", Some("x".to_owned()));
+        let output = write_passports(&passports);
+        assert_eq!("aaa:x zzz:1\n\nhgt:\n", output);
+        let reparsed = parse_passports(&output, Mode::Lenient).unwrap();
+        assert_eq!(written(&passports), written(&reparsed));
+    }
+
+    #[test]
+    fn csv_round_trip() {
+        let passports = parse_passports(
+            "pid:1 hcl:a,\"b\" xyz:2\n\nbyr:1980 cid:\n\nabc:3",
+            Mode::Lenient,
+        )
+        .unwrap();
+        let csv = to_csv(&passports);
+        assert_eq!(
+            "\
+byr,iyr,eyr,hgt,hcl,ecl,pid,cid,abc,xyz
+,,,,\"a,\"\"b\"\"\",,1,,,2
+1980,,,,,,,\"\",,
+,,,,,,,,3,
+",
+            csv
+        );
+        let reparsed = from_csv(&csv).unwrap();
+        assert_eq!(written(&passports), written(&reparsed));
+        assert_eq!(csv, to_csv(&reparsed));
+
+        let passports = input_generator(VALID).unwrap();
+        let reparsed = from_csv(&to_csv(&passports)).unwrap();
+        assert_eq!(written(&passports), written(&reparsed));
+        assert!(from_csv("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn csv_errors() {
+        let error = |input| {
+            let err = from_csv(input).unwrap_err();
+            (err.line, err.column, err.message)
+        };
+        assert_eq!(
+            (1, 5, "Duplicate column byr".to_owned()),
+            error("byr,byr\n")
+        );
+        assert_eq!(
+            (2, 1, "Expected 2 cells, got 1".to_owned()),
+            error("byr,pid\n1980\n")
+        );
+        assert_eq!(
+            (2, 6, "Invalid value \"1 2\"".to_owned()),
+            error("byr,pid\n1980,1 2\n")
+        );
+        assert_eq!(
+            (2, 1, "Unterminated quoted cell".to_owned()),
+            error("byr\n\"1980\n")
+        );
+        assert_eq!(
+            (2, 7, "Expected ',' after quoted cell".to_owned()),
+            error("byr\n\"1980\"x\n")
+        );
+        assert_eq!(
+            (2, 1, "Unquoted cell \"19\\\"80\" contains '\"'".to_owned()),
+            error("byr\n19\"80\n")
+        );
+    }
+
+    #[test]
+    fn validators_agree() {
+        let rules = RuleSet::default();
+        let base = "byr:1980 iyr:2012 eyr:2030 hgt:74in hcl:#623a2f ecl:grn pid:087499704";
+        let cases = [
+            ("byr", "1920", true),
+            ("byr", "2002", true),
+            ("byr", "2003", false),
+            ("byr", "01980", false),
+            ("byr", "+1980", false),
+            ("byr", "198", false),
+            ("iyr", "2010", true),
+            ("eyr", "2031", false),
+            ("hgt", "150cm", true),
+            ("hgt", "0150cm", false),
+            ("hgt", "+60in", false),
+            ("hgt", "77in", false),
+            ("hgt", "60", false),
+            ("hcl", "#abcdef", true),
+            ("hcl", "#ABCDEF", false),
+            ("ecl", "oth", true),
+            ("ecl", "xyz", false),
+            ("pid", "000000001", true),
+            ("pid", "0123456789", false),
+        ];
+        for &(field, value, valid) in &cases {
+            let mut passport = input_generator(base).unwrap().remove(0);
+            passport.set(field, Some(value.to_owned()));
+            assert_eq!(
+                (valid, valid),
+                (
+                    ValidatedPassport::try_from(&passport).is_ok(),
+                    rules.check(&passport).is_empty()
+                ),
+                "{}:{}",
+                field,
+                value
+            );
+        }
+    }
 }
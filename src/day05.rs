@@ -1,55 +1,501 @@
+use std::{error::Error, fmt};
+
 use aoc_runner_derive::*;
 
-#[derive(Debug, Copy, Clone)]
+use crate::{ParseError, SolveError};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct SeatId {
-    row: u8,
-    col: u8,
+    pub row: u16,
+    pub col: u16,
 }
 
 impl SeatId {
+    /// The seat's ID on the puzzle's plane.
     pub fn id(&self) -> u16 {
-        self.row as u16 * 8 + self.col as u16
+        BoardingPass::PUZZLE.id(*self)
     }
 }
 
-#[aoc_generator(day5)]
-pub fn input_generator(input: &str) -> Vec<SeatId> {
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BoardingPassError {
+    /// The plane's IDs wouldn't fit in 16 bits.
+    TooManySeats {
+        row_bits: u32,
+        col_bits: u32,
+    },
+    WrongLength {
+        expected: usize,
+        found: usize,
+    },
+    /// The character at `index`, counted in characters, isn't either of `expected`.
+    InvalidCharacter {
+        index: usize,
+        expected: (char, char),
+        found: char,
+    },
+    NoSuchSeat(SeatId),
+}
+
+impl fmt::Display for BoardingPassError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BoardingPassError::TooManySeats { row_bits, col_bits } => write!(
+                f,
+                "A plane with {} row bits and {} column bits has more than 2^16 seats",
+                row_bits, col_bits
+            ),
+            BoardingPassError::WrongLength { expected, found } => {
+                write!(f, "Expected {} characters, found {}", expected, found)
+            }
+            BoardingPassError::InvalidCharacter {
+                expected: (zero, one),
+                found,
+                ..
+            } => write!(f, "Expected {:?} or {:?}, found {:?}", zero, one, found),
+            BoardingPassError::NoSuchSeat(seat) => {
+                write!(
+                    f,
+                    "There is no seat at row {}, column {}",
+                    seat.row, seat.col
+                )
+            }
+        }
+    }
+}
+
+impl Error for BoardingPassError {}
+
+/// Converts between seats and boarding passes for a plane with `2^row_bits` rows of
+/// `2^col_bits` seats.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct BoardingPass {
+    row_bits: u32,
+    col_bits: u32,
+}
+
+impl BoardingPass {
+    /// The puzzle's plane, with 128 rows of 8 seats.
+    pub const PUZZLE: BoardingPass = BoardingPass {
+        row_bits: 7,
+        col_bits: 3,
+    };
+
+    pub fn new(row_bits: u32, col_bits: u32) -> Result<Self, BoardingPassError> {
+        if !matches!(row_bits.checked_add(col_bits), Some(bits) if bits <= 16) {
+            return Err(BoardingPassError::TooManySeats { row_bits, col_bits });
+        }
+        Ok(BoardingPass { row_bits, col_bits })
+    }
+
+    pub fn rows(&self) -> u32 {
+        1 << self.row_bits
+    }
+
+    pub fn cols(&self) -> u32 {
+        1 << self.col_bits
+    }
+
+    /// The length of every boarding pass.
+    pub fn len(&self) -> usize {
+        (self.row_bits + self.col_bits) as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn contains(&self, seat: SeatId) -> bool {
+        u32::from(seat.row) < self.rows() && u32::from(seat.col) < self.cols()
+    }
+
+    /// The seat's row followed by its column, as a single number.
+    pub fn id(&self, seat: SeatId) -> u16 {
+        // Shifted as a `u32`, as a plane can have 16 column bits.
+        (u32::from(seat.row) << self.col_bits | u32::from(seat.col)) as u16
+    }
+
+    /// The seat with the given ID, the inverse of `id`.
+    pub fn seat(&self, id: u16) -> Option<SeatId> {
+        let id = u32::from(id);
+        let seat = SeatId {
+            row: (id >> self.col_bits) as u16,
+            col: (id & (self.cols() - 1)) as u16,
+        };
+        Some(seat).filter(|&seat| self.contains(seat))
+    }
+
+    /// Writes the row in `F`s and `B`s and then the column in `L`s and `R`s, most significant
+    /// bit first.
+    pub fn encode(&self, seat: SeatId) -> Result<String, BoardingPassError> {
+        if !self.contains(seat) {
+            return Err(BoardingPassError::NoSuchSeat(seat));
+        }
+        let bits = |value: u16, width: u32, zero: char, one: char| {
+            (0..width)
+                .rev()
+                .map(move |bit| if value >> bit & 1 == 0 { zero } else { one })
+        };
+        Ok(bits(seat.row, self.row_bits, 'F', 'B')
+            .chain(bits(seat.col, self.col_bits, 'L', 'R'))
+            .collect())
+    }
+
+    pub fn decode(&self, pass: &str) -> Result<SeatId, BoardingPassError> {
+        let found = pass.chars().count();
+        if found != self.len() {
+            return Err(BoardingPassError::WrongLength {
+                expected: self.len(),
+                found,
+            });
+        }
+        let mut seat = SeatId { row: 0, col: 0 };
+        for (index, c) in pass.chars().enumerate() {
+            let (value, expected) = if index < self.row_bits as usize {
+                (&mut seat.row, ('F', 'B'))
+            } else {
+                (&mut seat.col, ('L', 'R'))
+            };
+            let bit = match c {
+                c if c == expected.0 => 0,
+                c if c == expected.1 => 1,
+                found => {
+                    return Err(BoardingPassError::InvalidCharacter {
+                        index,
+                        expected,
+                        found,
+                    })
+                }
+            };
+            *value = *value << 1 | bit;
+        }
+        Ok(seat)
+    }
+}
+
+/// Decodes one boarding pass per line.
+pub fn parse_passes(input: &str, plane: BoardingPass) -> Result<Vec<SeatId>, ParseError> {
     input
         .lines()
         .map(|line| {
-            let mut chars = line.chars();
-            let row = chars.by_ref().take(7).fold(0, |acc, c| {
-                (acc << 1)
-                    + match c {
-                        'F' => 0,
-                        'B' => 1,
-                        _ => panic!(),
-                    }
-            });
-
-            let col = chars.by_ref().take(3).fold(0, |acc, c| {
-                (acc << 1)
-                    + match c {
-                        'L' => 0,
-                        'R' => 1,
-                        _ => panic!(),
-                    }
-            });
-            SeatId { row, col }
+            plane.decode(line).map_err(|e| {
+                let at = match e {
+                    BoardingPassError::InvalidCharacter { index, .. } => line
+                        .char_indices()
+                        .nth(index)
+                        .map_or(line.len(), |(i, _)| i),
+                    _ => 0,
+                };
+                ParseError::at(5, input, &line[at..], e.to_string())
+            })
         })
         .collect()
 }
 
+#[aoc_generator(day5)]
+pub fn input_generator(input: &str) -> Result<Vec<SeatId>, ParseError> {
+    parse_passes(input, BoardingPass::PUZZLE)
+}
+
+/// How many boarding passes there are for each seat on a plane.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SeatMap {
+    plane: BoardingPass,
+    /// Indexed by seat ID.
+    passes: Vec<usize>,
+}
+
+/// The unoccupied seats, split by whether they're in front of, between or behind the occupied
+/// seats in ID order. Each list is in ID order.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct EmptySeats {
+    pub front: Vec<SeatId>,
+    pub interior: Vec<SeatId>,
+    pub back: Vec<SeatId>,
+}
+
+impl SeatMap {
+    pub fn new(plane: BoardingPass, seats: &[SeatId]) -> Result<Self, BoardingPassError> {
+        let mut passes = vec![0; (plane.rows() * plane.cols()) as usize];
+        for &seat in seats {
+            if !plane.contains(seat) {
+                return Err(BoardingPassError::NoSuchSeat(seat));
+            }
+            passes[plane.id(seat) as usize] += 1;
+        }
+        Ok(SeatMap { plane, passes })
+    }
+
+    pub fn plane(&self) -> BoardingPass {
+        self.plane
+    }
+
+    /// The number of boarding passes for the seat, or 0 if it isn't on the plane.
+    pub fn passes(&self, seat: SeatId) -> usize {
+        if self.plane.contains(seat) {
+            self.passes[self.plane.id(seat) as usize]
+        } else {
+            0
+        }
+    }
+
+    pub fn is_occupied(&self, seat: SeatId) -> bool {
+        self.passes(seat) > 0
+    }
+
+    fn seats(&self) -> impl Iterator<Item = (SeatId, usize)> + '_ {
+        self.passes
+            .iter()
+            .enumerate()
+            .map(move |(id, &passes)| (self.plane.seat(id as u16).unwrap(), passes))
+    }
+
+    /// The seats with more than one boarding pass, with how many each has, in ID order.
+    pub fn duplicates(&self) -> Vec<(SeatId, usize)> {
+        self.seats().filter(|&(_, passes)| passes > 1).collect()
+    }
+
+    /// Every unoccupied seat. If no seat is occupied they're all at the front.
+    pub fn empty_seats(&self) -> EmptySeats {
+        let first = self.passes.iter().position(|&passes| passes > 0);
+        let last = self.passes.iter().rposition(|&passes| passes > 0);
+        let mut empty = EmptySeats::default();
+        for (id, (seat, passes)) in self.seats().enumerate() {
+            if passes > 0 {
+                continue;
+            }
+            match first.zip(last) {
+                Some((first, last)) if first < id && id < last => empty.interior.push(seat),
+                Some((first, _)) if first < id => empty.back.push(seat),
+                _ => empty.front.push(seat),
+            }
+        }
+        empty
+    }
+}
+
+/// Draws a line per row, numbered, with `.` for empty seats, `#` for occupied ones, and the
+/// number of passes for seats with more than one, or `+` if there are more than 9. The columns
+/// are split by an aisle down the middle.
+impl fmt::Display for SeatMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let cols = self.plane.cols() as usize;
+        let width = (self.plane.rows() - 1).to_string().len();
+        for (row, passes) in self.passes.chunks(cols).enumerate() {
+            write!(f, "{:>width$} ", row, width = width)?;
+            for (col, &passes) in passes.iter().enumerate() {
+                if cols > 1 && col == cols / 2 {
+                    f.write_str(" ")?;
+                }
+                let c = match passes {
+                    0 => '.',
+                    1 => '#',
+                    2..=9 => char::from(b'0' + passes as u8),
+                    _ => '+',
+                };
+                write!(f, "{}", c)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
 #[aoc(day5, part1)]
-pub fn day5_part1(input: &[SeatId]) -> u16 {
-    input.iter().map(SeatId::id).max().unwrap()
+pub fn day5_part1(input: &[SeatId]) -> Result<u16, SolveError> {
+    input
+        .iter()
+        .map(SeatId::id)
+        .max()
+        .ok_or_else(|| SolveError::NoSolution("No boarding passes".to_owned()))
 }
 
 #[aoc(day5, part2)]
-pub fn day5_part2(input: &[SeatId]) -> u16 {
-    let mut ids: Vec<_> = input.iter().map(SeatId::id).collect();
-    ids.sort_unstable();
-    ids.array_windows()
-        .find_map(|&[a, b]| (a != b - 1).then_some(a + 1))
-        .unwrap()
+pub fn day5_part2(input: &[SeatId]) -> Result<u16, SolveError> {
+    let map = SeatMap::new(BoardingPass::PUZZLE, input)
+        .map_err(|e| SolveError::InvalidInput(e.to_string()))?;
+    map.empty_seats()
+        .interior
+        .first()
+        .map(SeatId::id)
+        .ok_or_else(|| SolveError::NoSolution("No gap between seat IDs".to_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parser() {
+        let seats = input_generator("BFFFBBFRRR\nFFFBBBFRRR\n").unwrap();
+        assert_eq!(
+            vec![567, 119],
+            seats.iter().map(SeatId::id).collect::<Vec<_>>()
+        );
+        let err = input_generator("BFFFBBFRRR\nFFFBBBFRLX\n").unwrap_err();
+        assert_eq!((2, 10), (err.line, err.column));
+    }
+
+    #[test]
+    fn codec() {
+        let plane = BoardingPass::PUZZLE;
+        for id in 0..1024 {
+            let seat = plane.seat(id).unwrap();
+            assert_eq!(id, seat.id());
+            assert_eq!(Ok(seat), plane.decode(&plane.encode(seat).unwrap()));
+        }
+        assert_eq!(None, plane.seat(1024));
+        assert_eq!(
+            Ok("BFFFBBFRRR".to_owned()),
+            plane.encode(SeatId { row: 70, col: 7 })
+        );
+        assert_eq!(
+            Err(BoardingPassError::NoSuchSeat(SeatId { row: 128, col: 0 })),
+            plane.encode(SeatId { row: 128, col: 0 })
+        );
+
+        let wide = BoardingPass::new(9, 4).unwrap();
+        let seat = SeatId { row: 300, col: 9 };
+        let pass = wide.encode(seat).unwrap();
+        assert_eq!("BFFBFBBFFRLLR", pass);
+        assert_eq!(Ok(seat), wide.decode(&pass));
+        assert_eq!(Some(seat), wide.seat(wide.id(seat)));
+        assert_eq!(
+            Err(BoardingPassError::WrongLength {
+                expected: 10,
+                found: 13
+            }),
+            plane.decode(&pass)
+        );
+        assert_eq!(
+            Err(BoardingPassError::InvalidCharacter {
+                index: 9,
+                expected: ('L', 'R'),
+                found: 'B'
+            }),
+            wide.decode("BFFBFBBFFBLLR")
+        );
+        assert_eq!(
+            Err(BoardingPassError::TooManySeats {
+                row_bits: 12,
+                col_bits: 5
+            }),
+            BoardingPass::new(12, 5)
+        );
+        let err = parse_passes("BFFBFBBFFRLLR\nBFFBFBBFFRLLé\n", wide).unwrap_err();
+        assert_eq!((2, 13), (err.line, err.column));
+    }
+
+    #[test]
+    fn seat_map() {
+        let plane = BoardingPass::new(3, 2).unwrap();
+        let seat = |row, col| SeatId { row, col };
+        let seats = [
+            seat(1, 2),
+            seat(1, 3),
+            seat(2, 0),
+            seat(2, 3),
+            seat(3, 0),
+            seat(3, 0),
+            seat(4, 1),
+            seat(5, 1),
+        ];
+        let map = SeatMap::new(plane, &seats).unwrap();
+        assert_eq!(2, map.passes(seat(3, 0)));
+        assert!(!map.is_occupied(seat(2, 1)));
+        assert_eq!(0, map.passes(seat(8, 0)));
+        assert_eq!(vec![(seat(3, 0), 2)], map.duplicates());
+        assert_eq!(
+            EmptySeats {
+                front: vec![
+                    seat(0, 0),
+                    seat(0, 1),
+                    seat(0, 2),
+                    seat(0, 3),
+                    seat(1, 0),
+                    seat(1, 1)
+                ],
+                interior: vec![
+                    seat(2, 1),
+                    seat(2, 2),
+                    seat(3, 1),
+                    seat(3, 2),
+                    seat(3, 3),
+                    seat(4, 0),
+                    seat(4, 2),
+                    seat(4, 3),
+                    seat(5, 0)
+                ],
+                back: vec![
+                    seat(5, 2),
+                    seat(5, 3),
+                    seat(6, 0),
+                    seat(6, 1),
+                    seat(6, 2),
+                    seat(6, 3),
+                    seat(7, 0),
+                    seat(7, 1),
+                    seat(7, 2),
+                    seat(7, 3)
+                ],
+            },
+            map.empty_seats()
+        );
+        assert_eq!(
+            "\
+0 .. ..
+1 .. ##
+2 #. .#
+3 2. ..
+4 .# ..
+5 .# ..
+6 .. ..
+7 .. ..
+",
+            map.to_string()
+        );
+        assert_eq!(
+            Err(BoardingPassError::NoSuchSeat(seat(0, 4))),
+            SeatMap::new(plane, &[seat(0, 4)])
+        );
+        let empty = SeatMap::new(plane, &[]).unwrap().empty_seats();
+        assert_eq!(
+            (32, 0, 0),
+            (empty.front.len(), empty.interior.len(), empty.back.len())
+        );
+    }
+
+    #[test]
+    fn single_gap() {
+        let seats: Vec<_> = (40..60)
+            .filter(|&id| id != 47)
+            .chain([52, 52])
+            .map(|id| BoardingPass::PUZZLE.seat(id).unwrap())
+            .collect();
+        assert_eq!(Ok(59), day5_part1(&seats));
+        assert_eq!(Ok(47), day5_part2(&seats));
+    }
+
+    #[test]
+    fn sixteen_bit_planes() {
+        let wide = BoardingPass::new(0, 16).unwrap();
+        let seat = SeatId { row: 0, col: 65535 };
+        assert_eq!(65535, wide.id(seat));
+        assert_eq!(Some(seat), wide.seat(65535));
+        assert_eq!("R".repeat(16), wide.encode(seat).unwrap());
+        assert_eq!(Ok(seat), wide.decode(&"R".repeat(16)));
+
+        let long = BoardingPass::new(16, 0).unwrap();
+        let seat = SeatId { row: 40000, col: 0 };
+        assert_eq!(40000, long.id(seat));
+        assert_eq!(Some(seat), long.seat(40000));
+        assert_eq!(Ok(seat), long.decode(&long.encode(seat).unwrap()));
+
+        for plane in [wide, long] {
+            let map =
+                SeatMap::new(plane, &[plane.seat(0).unwrap(), plane.seat(2).unwrap()]).unwrap();
+            assert_eq!(vec![plane.seat(1).unwrap()], map.empty_seats().interior);
+        }
+        assert!(BoardingPass::new(u32::MAX, 1).is_err());
+    }
 }
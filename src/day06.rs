@@ -1,10 +1,152 @@
+use std::{
+    collections::BTreeSet,
+    ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign},
+};
+
 use aoc_runner_derive::*;
 
-#[derive(Debug, Default, Copy, Clone)]
-pub struct Answers(pub [bool; 26]);
+use crate::{ParseError, SolveError};
 
-#[aoc_generator(day6)]
-pub fn input_generator(input: &str) -> Vec<Vec<Answers>> {
+/// The most questions an `Alphabet` can have.
+pub const MAX_QUESTIONS: usize = 128;
+
+/// The set of questions someone answered "yes" to, as a bit per question index.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Answers(pub u128);
+
+impl Answers {
+    /// Every one of the first `questions` questions.
+    pub fn all(questions: usize) -> Self {
+        assert!(questions <= MAX_QUESTIONS, "Too many questions");
+        let unused = (MAX_QUESTIONS - questions) as u32;
+        Answers(u128::MAX.checked_shr(unused).unwrap_or(0))
+    }
+
+    pub fn len(self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn contains(self, question: usize) -> bool {
+        question < MAX_QUESTIONS && self.0 >> question & 1 == 1
+    }
+
+    pub fn insert(&mut self, question: usize) {
+        assert!(question < MAX_QUESTIONS, "Too many questions");
+        self.0 |= 1 << question;
+    }
+
+    /// The indices of the questions, in increasing order.
+    pub fn iter(self) -> impl Iterator<Item = usize> {
+        (0..MAX_QUESTIONS).filter(move |&question| self.contains(question))
+    }
+
+    /// The questions anyone in the group answered.
+    pub fn union(group: &[Answers]) -> Self {
+        group
+            .iter()
+            .fold(Answers::default(), |acc, &answers| acc | answers)
+    }
+
+    /// The questions everyone in the group answered, or none for an empty group.
+    pub fn intersection(group: &[Answers]) -> Self {
+        group
+            .iter()
+            .copied()
+            .reduce(|acc, answers| acc & answers)
+            .unwrap_or_default()
+    }
+}
+
+impl BitOr for Answers {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Answers(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for Answers {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl BitAnd for Answers {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self::Output {
+        Answers(self.0 & rhs.0)
+    }
+}
+
+impl BitAndAssign for Answers {
+    fn bitand_assign(&mut self, rhs: Self) {
+        self.0 &= rhs.0;
+    }
+}
+
+/// The characters used for questions, each standing for the question at its index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alphabet {
+    questions: Vec<char>,
+}
+
+impl Alphabet {
+    /// The puzzle's questions, `a` to `z`.
+    pub fn lowercase() -> Self {
+        Alphabet {
+            questions: ('a'..='z').collect(),
+        }
+    }
+
+    /// Every character other than whitespace used in the input, in order.
+    pub fn discover(input: &str) -> Result<Self, ParseError> {
+        let questions: BTreeSet<char> = input.chars().filter(|c| !c.is_whitespace()).collect();
+        if questions.len() > MAX_QUESTIONS {
+            return Err(ParseError::at(
+                6,
+                input,
+                &input[input.len()..],
+                format!(
+                    "Found {} different questions, at most {} are supported",
+                    questions.len(),
+                    MAX_QUESTIONS
+                ),
+            ));
+        }
+        Ok(Alphabet {
+            questions: questions.into_iter().collect(),
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.questions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.questions.is_empty()
+    }
+
+    pub fn index(&self, question: char) -> Option<usize> {
+        self.questions.binary_search(&question).ok()
+    }
+
+    pub fn question(&self, index: usize) -> Option<char> {
+        self.questions.get(index).copied()
+    }
+
+    /// The characters for the questions in `answers`.
+    pub fn questions(&self, answers: Answers) -> impl Iterator<Item = char> + '_ {
+        answers.iter().filter_map(move |index| self.question(index))
+    }
+}
+
+/// Reads groups separated by blank lines, with a line of answers per person.
+pub fn parse_groups(input: &str, alphabet: &Alphabet) -> Result<Vec<Vec<Answers>>, ParseError> {
     let mut groups = Vec::new();
     let mut group_answers = Vec::new();
     for line in input.lines() {
@@ -13,49 +155,124 @@ pub fn input_generator(input: &str) -> Vec<Vec<Answers>> {
             group_answers = Vec::new();
         } else {
             let mut answers = Answers::default();
-            for c in line.chars() {
-                match c {
-                    'a'..='z' => answers.0[c as usize - 'a' as usize] = true,
-                    _ => panic!("Unexpected answer: {:?}", c),
+            for (i, c) in line.char_indices() {
+                match alphabet.index(c) {
+                    Some(question) => answers.insert(question),
+                    None => {
+                        return Err(ParseError::at(
+                            6,
+                            input,
+                            &line[i..],
+                            format!("Unexpected answer: {:?}", c),
+                        ))
+                    }
                 }
             }
             group_answers.push(answers);
         }
     }
     groups.push(group_answers);
-    groups
+    Ok(groups)
 }
 
-#[aoc(day6, part1)]
-pub fn day6_part1(input: &[Vec<Answers>]) -> usize {
-    input
-        .iter()
-        .map(|group_answers| {
-            let mut answers = Answers::default();
-            for individual_answers in group_answers {
-                for (answer, individual_answer) in answers.0.iter_mut().zip(&individual_answers.0) {
-                    *answer |= individual_answer;
-                }
+/// Reads groups like `parse_groups`, using the alphabet discovered from the input.
+pub fn parse_survey(input: &str) -> Result<(Alphabet, Vec<Vec<Answers>>), ParseError> {
+    let alphabet = Alphabet::discover(input)?;
+    let groups = parse_groups(input, &alphabet)?;
+    Ok((alphabet, groups))
+}
+
+/// How many people answered each of a survey's questions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Statistics {
+    pub people: usize,
+    /// Indexed by question.
+    pub yes_counts: Vec<usize>,
+}
+
+impl Statistics {
+    /// Counts the answers to the first `questions` questions.
+    pub fn new<'a>(questions: usize, people: impl IntoIterator<Item = &'a Answers>) -> Self {
+        let mut statistics = Statistics {
+            people: 0,
+            yes_counts: vec![0; questions],
+        };
+        for answers in people {
+            statistics.people += 1;
+            for question in answers.iter() {
+                assert!(
+                    question < questions,
+                    "Answer to unknown question {}",
+                    question
+                );
+                statistics.yes_counts[question] += 1;
             }
-            answers.0.iter().filter(|&&b| b).count()
-        })
-        .sum()
+        }
+        statistics
+    }
+
+    /// The statistics for each group, and then for everyone in every group.
+    pub fn survey(questions: usize, groups: &[Vec<Answers>]) -> (Vec<Self>, Self) {
+        let per_group = groups
+            .iter()
+            .map(|group| Statistics::new(questions, group))
+            .collect();
+        (
+            per_group,
+            Statistics::new(questions, groups.iter().flatten()),
+        )
+    }
+
+    pub fn yes_count(&self, question: usize) -> usize {
+        self.yes_counts.get(question).copied().unwrap_or(0)
+    }
+
+    fn questions_where(&self, keep: impl Fn(usize) -> bool) -> Answers {
+        let mut answers = Answers::default();
+        for (question, &count) in self.yes_counts.iter().enumerate() {
+            if keep(count) {
+                answers.insert(question);
+            }
+        }
+        answers
+    }
+
+    /// The questions exactly `k` people answered.
+    pub fn exactly(&self, k: usize) -> Answers {
+        self.questions_where(|count| count == k)
+    }
+
+    /// The questions at least `k` people answered.
+    pub fn at_least(&self, k: usize) -> Answers {
+        self.questions_where(|count| count >= k)
+    }
+
+    /// The questions at least `percent`% of the people answered. With nobody asked, that's
+    /// none of them.
+    pub fn at_least_percent(&self, percent: u32) -> Answers {
+        if self.people == 0 {
+            return Answers::default();
+        }
+        self.questions_where(|count| count * 100 >= percent as usize * self.people)
+    }
+}
+
+#[aoc_generator(day6)]
+pub fn input_generator(input: &str) -> Result<Vec<Vec<Answers>>, ParseError> {
+    parse_groups(input, &Alphabet::lowercase())
+}
+
+#[aoc(day6, part1)]
+pub fn day6_part1(input: &[Vec<Answers>]) -> Result<usize, SolveError> {
+    Ok(input.iter().map(|group| Answers::union(group).len()).sum())
 }
 
 #[aoc(day6, part2)]
-pub fn day6_part2(input: &[Vec<Answers>]) -> usize {
-    input
+pub fn day6_part2(input: &[Vec<Answers>]) -> Result<usize, SolveError> {
+    Ok(input
         .iter()
-        .map(|group_answers| {
-            let mut answers = Answers([true; 26]);
-            for individual_answers in group_answers {
-                for (answer, individual_answer) in answers.0.iter_mut().zip(&individual_answers.0) {
-                    *answer &= individual_answer;
-                }
-            }
-            answers.0.iter().filter(|&&b| b).count()
-        })
-        .sum()
+        .map(|group| Answers::intersection(group).len())
+        .sum())
 }
 
 #[cfg(test)]
@@ -65,16 +282,95 @@ mod tests {
     #[test]
     fn part1() {
         let input = "abc\n\na\nb\nc\n\nab\nac\n\na\na\na\na\n\nb\n";
-        let parsed = input_generator(input);
-        let answer = day6_part1(&parsed);
+        let parsed = input_generator(input).unwrap();
+        let answer = day6_part1(&parsed).unwrap();
         assert_eq!(11, answer)
     }
 
     #[test]
     fn part2() {
         let input = "abc\n\na\nb\nc\n\nab\nac\n\na\na\na\na\n\nb\n";
-        let parsed = input_generator(input);
-        let answer = day6_part2(&parsed);
+        let parsed = input_generator(input).unwrap();
+        let answer = day6_part2(&parsed).unwrap();
         assert_eq!(6, answer)
     }
+
+    #[test]
+    fn bitset() {
+        assert_eq!(Answers(0), Answers::all(0));
+        assert_eq!(Answers(0b111), Answers::all(3));
+        assert_eq!(Answers(u128::MAX), Answers::all(MAX_QUESTIONS));
+        let a = Answers(0b0110);
+        let b = Answers(0b1100);
+        assert_eq!(Answers(0b1110), a | b);
+        assert_eq!(Answers(0b0100), a & b);
+        assert_eq!(vec![1, 2], a.iter().collect::<Vec<_>>());
+        assert!(a.contains(2) && !a.contains(3) && !a.contains(500));
+        assert_eq!(Answers::default(), Answers::intersection(&[]));
+    }
+
+    #[test]
+    fn custom_alphabet() {
+        let input = "AB1\nB1é\n\nzé\n";
+        assert!(input_generator(input).is_err());
+        let (alphabet, groups) = parse_survey(input).unwrap();
+        assert_eq!(
+            "1ABzé",
+            alphabet.questions(Answers::all(5)).collect::<String>()
+        );
+        let union = Answers::union(&groups[0]);
+        let intersection = Answers::intersection(&groups[0]);
+        assert_eq!("1ABé", alphabet.questions(union).collect::<String>());
+        assert_eq!("1B", alphabet.questions(intersection).collect::<String>());
+        assert_eq!(Ok(6), day6_part1(&groups));
+        assert_eq!(Ok(4), day6_part2(&groups));
+
+        let err = parse_groups("ab\nc d\n", &Alphabet::discover("abcd").unwrap()).unwrap_err();
+        assert_eq!(
+            (2, 2, "Unexpected answer: ' '"),
+            (err.line, err.column, &err.message[..])
+        );
+        let many: String = ('\u{100}'..'\u{200}').collect();
+        let err = Alphabet::discover(&many).unwrap_err();
+        assert_eq!((1, 257), (err.line, err.column));
+    }
+
+    #[test]
+    fn statistics() {
+        let input = "abc\n\na\nb\nc\n\nab\nac\n\na\na\na\na\n\nb\n";
+        let groups = input_generator(input).unwrap();
+        let (per_group, overall) = Statistics::survey(26, &groups);
+        assert_eq!(
+            Ok(per_group.iter().map(|group| group.at_least(1).len()).sum()),
+            day6_part1(&groups)
+        );
+        assert_eq!(
+            Ok(per_group
+                .iter()
+                .map(|group| group.exactly(group.people).len())
+                .sum()),
+            day6_part2(&groups)
+        );
+
+        let third = &per_group[2];
+        assert_eq!(
+            (2, 2, 1, 1),
+            (
+                third.people,
+                third.yes_count(0),
+                third.yes_count(1),
+                third.yes_count(2)
+            )
+        );
+        assert_eq!(Answers(0b110), third.exactly(1));
+        assert_eq!(Answers(0b111), third.at_least_percent(50));
+        assert_eq!(Answers(0b001), third.at_least_percent(51));
+
+        assert_eq!(11, overall.people);
+        assert_eq!(vec![8, 4, 3], overall.yes_counts[..3].to_vec());
+        assert_eq!(23, overall.exactly(0).len());
+        assert_eq!(Answers(0b001), overall.at_least_percent(70));
+        assert_eq!(Answers(0b011), overall.at_least(4));
+        assert!(Statistics::new(26, &[]).at_least_percent(0).is_empty());
+    }
 }
@@ -3,65 +3,231 @@ use std::collections::{HashMap, HashSet};
 use aoc_runner_derive::*;
 use regex::Regex;
 
-#[derive(Debug, Clone)]
-struct Colour {
-    modifier: String,
-    hue: String,
-}
+use crate::{ParseError, SolveError};
 
-#[aoc_generator(day7)]
-pub fn input_generator(input: &str) -> HashMap<String, Vec<(usize, String)>> {
-    let bag_regex = Regex::new(r"(\d+) (\w+ \w+)").unwrap();
-    input
-        .lines()
-        .map(|line| {
-            let outer_bag = line[..line.match_indices(' ').nth(1).unwrap().0].to_owned();
-            let inner_bags = bag_regex
-                .captures_iter(line)
-                .map(|bag| (bag[1].parse().unwrap(), bag[2].to_owned()))
-                .collect();
-            (outer_bag, inner_bags)
-        })
-        .collect()
+/// An index into a `BagGraph`'s colours.
+pub type BagId = usize;
+
+/// The rules for which bags contain which, with each colour interned as a `BagId`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BagGraph {
+    colours: Vec<String>,
+    ids: HashMap<String, BagId>,
+    /// The number of each bag directly inside each bag.
+    contents: Vec<Vec<(usize, BagId)>>,
+    /// The bags directly containing each bag.
+    containers: Vec<Vec<BagId>>,
 }
 
-#[aoc(day7, part1)]
-pub fn day7_part1(input: &HashMap<String, Vec<(usize, String)>>) -> usize {
-    let mut reversed: HashMap<_, Vec<_>> = HashMap::new();
-    for (outer, inners) in input.clone() {
-        for (_, inner) in inners {
-            reversed.entry(inner).or_default().push(outer.clone());
+impl BagGraph {
+    /// The colour's ID, adding it if it's new.
+    pub fn intern(&mut self, colour: &str) -> BagId {
+        if let Some(&id) = self.ids.get(colour) {
+            return id;
+        }
+        let id = self.colours.len();
+        self.colours.push(colour.to_owned());
+        self.ids.insert(colour.to_owned(), id);
+        self.contents.push(Vec::new());
+        self.containers.push(Vec::new());
+        id
+    }
+
+    /// Records that every `outer` bag directly contains `count` `inner` bags.
+    pub fn add_rule(&mut self, outer: BagId, count: usize, inner: BagId) {
+        self.contents[outer].push((count, inner));
+        if !self.containers[inner].contains(&outer) {
+            self.containers[inner].push(outer);
         }
     }
-    let mut set = HashSet::new();
-    let mut added = Vec::new();
-    set.extend(&reversed["shiny gold"]);
-    added.extend(&reversed["shiny gold"]);
-    while let Some(next) = added.pop() {
-        if let Some(outers) = reversed.get(next) {
-            for outer in outers {
-                if set.insert(outer) {
+
+    pub fn id(&self, colour: &str) -> Option<BagId> {
+        self.ids.get(colour).copied()
+    }
+
+    pub fn colour(&self, bag: BagId) -> &str {
+        &self.colours[bag]
+    }
+
+    pub fn len(&self) -> usize {
+        self.colours.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.colours.is_empty()
+    }
+
+    pub fn contents(&self, bag: BagId) -> &[(usize, BagId)] {
+        &self.contents[bag]
+    }
+
+    pub fn containers(&self, bag: BagId) -> &[BagId] {
+        &self.containers[bag]
+    }
+
+    /// Every bag that eventually contains the bag.
+    pub fn ancestors(&self, bag: BagId) -> HashSet<BagId> {
+        let mut found = HashSet::new();
+        let mut added = vec![bag];
+        while let Some(next) = added.pop() {
+            for &outer in self.containers(next) {
+                if found.insert(outer) {
                     added.push(outer);
                 }
             }
         }
+        found
+    }
+
+    /// Visits every bag reachable from `start` after all the bags inside it, or returns the
+    /// first cycle found, each bag in it directly containing the next and the last containing
+    /// the first.
+    fn post_order(
+        &self,
+        start: impl IntoIterator<Item = BagId>,
+        mut visit: impl FnMut(BagId),
+    ) -> Result<(), Vec<BagId>> {
+        #[derive(Copy, Clone, PartialEq, Eq)]
+        enum State {
+            New,
+            Open,
+            Done,
+        }
+        let mut states = vec![State::New; self.len()];
+        // Each open bag, with how many of its contents have been explored.
+        let mut stack: Vec<(BagId, usize)> = Vec::new();
+        for root in start {
+            if states[root] != State::New {
+                continue;
+            }
+            states[root] = State::Open;
+            stack.push((root, 0));
+            while let Some((bag, explored)) = stack.last_mut() {
+                let bag = *bag;
+                match self.contents(bag).get(*explored) {
+                    Some(&(_, inner)) => {
+                        *explored += 1;
+                        match states[inner] {
+                            State::New => {
+                                states[inner] = State::Open;
+                                stack.push((inner, 0));
+                            }
+                            State::Open => {
+                                let from = stack.iter().position(|&(open, _)| open == inner);
+                                return Err(stack[from.unwrap()..]
+                                    .iter()
+                                    .map(|&(open, _)| open)
+                                    .collect());
+                            }
+                            State::Done => {}
+                        }
+                    }
+                    None => {
+                        states[bag] = State::Done;
+                        stack.pop();
+                        visit(bag);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Every bag, each before all the bags it contains, or a cycle if there's no such order.
+    pub fn topological_order(&self) -> Result<Vec<BagId>, Vec<BagId>> {
+        let mut order = Vec::with_capacity(self.len());
+        self.post_order(0..self.len(), |bag| order.push(bag))?;
+        order.reverse();
+        Ok(order)
+    }
+
+    /// Some bags that contain themselves, each directly containing the next and the last
+    /// containing the first.
+    pub fn find_cycle(&self) -> Option<Vec<BagId>> {
+        self.post_order(0..self.len(), |_| {}).err()
+    }
+
+    /// The total number of bags inside the bag, or a cycle of bags inside it that contain
+    /// themselves. Each bag's total is only worked out once.
+    pub fn count_inside(&self, bag: BagId) -> Result<usize, Vec<BagId>> {
+        let mut totals = vec![0; self.len()];
+        self.post_order([bag], |outer| {
+            totals[outer] = self
+                .contents(outer)
+                .iter()
+                .map(|&(count, inner)| count * (1 + totals[inner]))
+                .sum();
+        })?;
+        Ok(totals[bag])
+    }
+
+    /// Describes a cycle returned by the graph, like `a -> b -> a`.
+    pub fn describe_cycle(&self, cycle: &[BagId]) -> String {
+        cycle
+            .iter()
+            .chain(cycle.first())
+            .map(|&bag| self.colour(bag))
+            .collect::<Vec<_>>()
+            .join(" -> ")
     }
-    set.len()
 }
 
-#[aoc(day7, part2)]
-pub fn day7_part2(input: &HashMap<String, Vec<(usize, String)>>) -> usize {
-    bags_in_bag(input, "shiny gold")
+#[aoc_generator(day7)]
+pub fn input_generator(input: &str) -> Result<BagGraph, ParseError> {
+    let bag_regex = Regex::new(r"(\d+) (\w+ \w+)").unwrap();
+    let mut graph = BagGraph::default();
+    let mut defined = HashSet::new();
+    for line in input.lines() {
+        let outer = match line.match_indices(' ').nth(1) {
+            Some((end, _)) => graph.intern(&line[..end]),
+            None => {
+                return Err(ParseError::at(
+                    7,
+                    input,
+                    line,
+                    "Expected a two word bag colour",
+                ))
+            }
+        };
+        if !defined.insert(outer) {
+            return Err(ParseError::at(
+                7,
+                input,
+                line,
+                format!("Duplicate rule for {} bags", graph.colour(outer)),
+            ));
+        }
+        for bag in bag_regex.captures_iter(line) {
+            let count = bag.get(1).unwrap().as_str();
+            let count = count.parse().map_err(|e| {
+                ParseError::at(7, input, count, format!("Invalid bag count: {}", e))
+            })?;
+            let inner = graph.intern(&bag[2]);
+            graph.add_rule(outer, count, inner);
+        }
+    }
+    Ok(graph)
+}
+
+fn shiny_gold(input: &BagGraph) -> Result<BagId, SolveError> {
+    input
+        .id("shiny gold")
+        .ok_or_else(|| SolveError::InvalidInput("No rule for shiny gold bags".to_owned()))
 }
 
-fn bags_in_bag(input: &HashMap<String, Vec<(usize, String)>>, bag: &str) -> usize {
-    let bags = match input.get(bag) {
-        Some(bags) => bags,
-        None => return 0,
-    };
-    bags.iter()
-        .map(|(num, bag)| num * (1 + bags_in_bag(input, bag)))
-        .sum()
+#[aoc(day7, part1)]
+pub fn day7_part1(input: &BagGraph) -> Result<usize, SolveError> {
+    Ok(input.ancestors(shiny_gold(input)?).len())
+}
+
+#[aoc(day7, part2)]
+pub fn day7_part2(input: &BagGraph) -> Result<usize, SolveError> {
+    input.count_inside(shiny_gold(input)?).map_err(|cycle| {
+        SolveError::InvalidInput(format!(
+            "Bags contain themselves: {}",
+            input.describe_cycle(&cycle)
+        ))
+    })
 }
 
 #[cfg(test)]
@@ -92,22 +258,109 @@ mod tests {
 
     #[test]
     fn part1() {
-        let input = input_generator(TEST_INPUT_1);
-        let answer = day7_part1(&input);
+        let input = input_generator(TEST_INPUT_1).unwrap();
+        let answer = day7_part1(&input).unwrap();
         assert_eq!(4, answer);
     }
 
     #[test]
     fn part2_input1() {
-        let input = input_generator(TEST_INPUT_1);
-        let answer = day7_part2(&input);
+        let input = input_generator(TEST_INPUT_1).unwrap();
+        let answer = day7_part2(&input).unwrap();
         assert_eq!(32, answer);
     }
 
     #[test]
     fn part2_input2() {
-        let input = input_generator(TEST_INPUT_2);
-        let answer = day7_part2(&input);
+        let input = input_generator(TEST_INPUT_2).unwrap();
+        let answer = day7_part2(&input).unwrap();
         assert_eq!(126, answer);
     }
+
+    #[test]
+    fn part2_cycle() {
+        let input = input_generator(
+            "shiny gold bags contain 1 dark red bag.\ndark red bags contain 2 shiny gold bags.\n",
+        )
+        .unwrap();
+        assert!(matches!(
+            day7_part2(&input),
+            Err(SolveError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn graph() {
+        let graph = input_generator(TEST_INPUT_1).unwrap();
+        assert_eq!(9, graph.len());
+        let id = |colour| graph.id(colour).unwrap();
+        assert_eq!("shiny gold", graph.colour(id("shiny gold")));
+        assert_eq!(
+            &[(1, id("dark olive")), (2, id("vibrant plum"))],
+            graph.contents(id("shiny gold"))
+        );
+        assert_eq!(
+            &[id("muted yellow"), id("dark olive"), id("vibrant plum")],
+            graph.containers(id("faded blue"))
+        );
+
+        let order = graph.topological_order().unwrap();
+        assert_eq!(graph.len(), order.len());
+        let position = |bag| order.iter().position(|&other| other == bag).unwrap();
+        for outer in 0..graph.len() {
+            for &(_, inner) in graph.contents(outer) {
+                assert!(position(outer) < position(inner));
+            }
+        }
+        assert_eq!(None, graph.find_cycle());
+        assert_eq!(Ok(0), graph.count_inside(id("faded blue")));
+        assert_eq!(Ok(11), graph.count_inside(id("vibrant plum")));
+    }
+
+    #[test]
+    fn cycles() {
+        let graph = input_generator(
+            "\
+            shiny gold bags contain 1 dark red bag.\n\
+            dark red bags contain 1 pale green bag, 2 dark blue bags.\n\
+            pale green bags contain 2 dark red bags.\n\
+            dark blue bags contain 1 dark blue bag.\n\
+            faded blue bags contain no other bags.\n\
+            ",
+        )
+        .unwrap();
+        let cycle = graph.find_cycle().unwrap();
+        assert_eq!(
+            "dark red -> pale green -> dark red",
+            graph.describe_cycle(&cycle)
+        );
+        assert_eq!(Err(cycle), graph.topological_order());
+        assert_eq!(
+            Err(SolveError::InvalidInput(
+                "Bags contain themselves: dark red -> pale green -> dark red".to_owned()
+            )),
+            day7_part2(&graph)
+        );
+        let cycle = graph
+            .count_inside(graph.id("dark blue").unwrap())
+            .unwrap_err();
+        assert_eq!("dark blue -> dark blue", graph.describe_cycle(&cycle));
+        assert_eq!(Ok(0), graph.count_inside(graph.id("faded blue").unwrap()));
+
+        let err = input_generator(
+            "faded blue bags contain no other bags.\nfaded blue bags contain 1 dark red bag.\n",
+        )
+        .unwrap_err();
+        assert_eq!((2, 1), (err.line, err.column));
+    }
+
+    #[test]
+    fn missing_shiny_gold() {
+        let input = input_generator("faded blue bags contain no other bags.\n").unwrap();
+        let missing = Err(SolveError::InvalidInput(
+            "No rule for shiny gold bags".to_owned(),
+        ));
+        assert_eq!(missing, day7_part1(&input));
+        assert_eq!(missing, day7_part2(&input));
+    }
 }
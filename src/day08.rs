@@ -2,6 +2,8 @@ use std::str::FromStr;
 
 use aoc_runner_derive::*;
 
+use crate::{ParseError, SolveError};
+
 #[derive(Debug)]
 pub struct Program {
     instructions: Vec<Instruction>,
@@ -90,29 +92,37 @@ impl FromStr for Opcode {
 }
 
 #[aoc_generator(day8)]
-pub fn input_generator(input: &str) -> Vec<Instruction> {
+pub fn input_generator(input: &str) -> Result<Vec<Instruction>, ParseError> {
     input
         .lines()
-        .map(|line| line.parse())
-        .collect::<Result<_, _>>()
-        .unwrap()
+        .map(|line| line.parse().map_err(|e| ParseError::at(8, input, line, e)))
+        .collect()
 }
 
 #[aoc(day8, part1)]
-pub fn day8_part1(input: &[Instruction]) -> i32 {
+pub fn day8_part1(input: &[Instruction]) -> Result<i32, SolveError> {
+    if input.is_empty() {
+        return Err(SolveError::InvalidInput("Program is empty".to_owned()));
+    }
     let mut program = Program::new(input.to_vec());
     let mut visited = vec![false; input.len()];
     loop {
         let state = program.step();
+        if !(0..input.len() as isize).contains(&state.program_counter) {
+            return Err(SolveError::NoSolution(format!(
+                "Program jumped to {} without looping",
+                state.program_counter
+            )));
+        }
         if visited[state.program_counter as usize] {
-            break state.accumulator;
+            break Ok(state.accumulator);
         }
         visited[state.program_counter as usize] = true;
     }
 }
 
 #[aoc(day8, part2)]
-pub fn day8_part2(input: &[Instruction]) -> i32 {
+pub fn day8_part2(input: &[Instruction]) -> Result<i32, SolveError> {
     'outer: for i in 0..input.len() {
         let mut instructions = input.to_vec();
         match instructions[i].opcode {
@@ -125,13 +135,13 @@ pub fn day8_part2(input: &[Instruction]) -> i32 {
         loop {
             let state = program.step();
             if state.program_counter >= program.instructions.len() as isize {
-                return state.accumulator;
+                return Ok(state.accumulator);
             }
-            if visited[state.program_counter as usize] {
+            if state.program_counter < 0 || visited[state.program_counter as usize] {
                 continue 'outer;
             }
             visited[state.program_counter as usize] = true;
         }
     }
-    panic!("No working change found!");
+    Err(SolveError::NoSolution("No working change found".to_owned()))
 }
@@ -2,13 +2,22 @@ use std::cmp::Ordering;
 
 use aoc_runner_derive::*;
 
+use crate::{ParseError, SolveError};
+
 #[aoc_generator(day9)]
-pub fn input_generator(input: &str) -> Vec<u64> {
-    input.lines().map(|line| line.parse().unwrap()).collect()
+pub fn input_generator(input: &str) -> Result<Vec<u64>, ParseError> {
+    input
+        .lines()
+        .map(|line| {
+            line.parse().map_err(|e| {
+                ParseError::at(9, input, line, format!("Invalid number {:?}: {}", line, e))
+            })
+        })
+        .collect()
 }
 
 #[aoc(day9, part1)]
-pub fn day9_part1(input: &[u64]) -> u64 {
+pub fn day9_part1(input: &[u64]) -> Result<u64, SolveError> {
     input
         .windows(26)
         .find_map(|numbers| {
@@ -22,24 +31,28 @@ pub fn day9_part1(input: &[u64]) -> u64 {
             }
             Some(target)
         })
-        .unwrap()
+        .ok_or_else(|| {
+            SolveError::NoSolution("Every number is a sum of two of the previous 25".to_owned())
+        })
 }
 
 #[aoc(day9, part2)]
-pub fn day9_part2(input: &[u64]) -> u64 {
-    let target = day9_part1(input);
+pub fn day9_part2(input: &[u64]) -> Result<u64, SolveError> {
+    let target = day9_part1(input)?;
     let mut start = 0;
-    let mut end = 1;
-    let mut sum = input[0];
+    let mut end = 0;
+    let mut sum = 0;
     loop {
         match sum.cmp(&target) {
-            Ordering::Less => {
-                sum += input[end];
-                end += 1;
+            Ordering::Equal if end - start >= 2 => {
+                return Ok(input[start..end].iter().copied().min().unwrap()
+                    + input[start..end].iter().copied().max().unwrap())
             }
-            Ordering::Equal => {
-                return input[start..end].iter().copied().min().unwrap()
-                    + input[start..end].iter().copied().max().unwrap()
+            Ordering::Less | Ordering::Equal => {
+                sum += input.get(end).ok_or_else(|| {
+                    SolveError::NoSolution(format!("No contiguous range sums to {}", target))
+                })?;
+                end += 1;
             }
             Ordering::Greater => {
                 sum -= input[start];
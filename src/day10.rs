@@ -2,28 +2,44 @@ use std::collections::{BTreeMap, HashMap};
 
 use aoc_runner_derive::*;
 
+use crate::{ParseError, SolveError};
+
 #[aoc_generator(day10)]
-pub fn input_generator(input: &str) -> Vec<u64> {
-    input.lines().map(|line| line.parse().unwrap()).collect()
+pub fn input_generator(input: &str) -> Result<Vec<u64>, ParseError> {
+    input
+        .lines()
+        .map(|line| {
+            line.parse().map_err(|e| {
+                ParseError::at(10, input, line, format!("Invalid number {:?}: {}", line, e))
+            })
+        })
+        .collect()
 }
 
 #[aoc(day10, part1)]
-pub fn day10_part1(input: &[u64]) -> u64 {
+pub fn day10_part1(input: &[u64]) -> Result<u64, SolveError> {
     let mut adapters = vec![0];
     adapters.extend_from_slice(input);
     adapters.sort_unstable();
-    let counts = adapters.array_windows().map(|&[a, b]| b - a).fold(
+    let counts = adapters.windows(2).map(|pair| pair[1] - pair[0]).fold(
         HashMap::<u64, u64>::new(),
         |mut acc, diff| {
             *acc.entry(diff).or_default() += 1;
             acc
         },
     );
-    counts[&1] * (counts[&3] + 1)
+    if let Some(gap) = counts.keys().find(|&&diff| diff > 3) {
+        return Err(SolveError::NoSolution(format!(
+            "Adapters differ by {} jolts",
+            gap
+        )));
+    }
+    let count = |diff| counts.get(&diff).copied().unwrap_or(0);
+    Ok(count(1) * (count(3) + 1))
 }
 
 #[aoc(day10, part2)]
-pub fn day10_part2(input: &[u64]) -> u64 {
+pub fn day10_part2(input: &[u64]) -> Result<u64, SolveError> {
     let mut adapters = BTreeMap::new();
     adapters.insert(0, 1);
     let mut values = input.to_vec();
@@ -41,7 +57,7 @@ pub fn day10_part2(input: &[u64]) -> u64 {
         let prev_3 = get(&adapters, value, 3);
         adapters.insert(value, prev_1 + prev_2 + prev_3);
     }
-    adapters.pop_last().unwrap().1
+    Ok(adapters.pop_last().unwrap().1)
 }
 
 #[cfg(test)]
@@ -52,11 +68,11 @@ mod tests {
 
     #[test]
     fn short_input_part1() {
-        assert_eq!(35, day10_part1(&INPUT1));
+        assert_eq!(Ok(35), day10_part1(&INPUT1));
     }
 
     #[test]
     fn short_input_part2() {
-        assert_eq!(8, day10_part2(&INPUT1));
+        assert_eq!(Ok(8), day10_part2(&INPUT1));
     }
 }
@@ -1,7 +1,12 @@
-use std::{fmt::Display, ops::RangeInclusive};
+use std::ops::RangeInclusive;
 
 use aoc_runner_derive::*;
 
+use crate::{
+    grid::{Cell, Connectivity, Grid, Pos},
+    ParseError, SolveError,
+};
+
 #[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq)]
 pub enum Position {
     Floor,
@@ -9,144 +14,87 @@ pub enum Position {
     Person,
 }
 
-#[derive(Debug, Clone)]
-pub struct Grid {
-    grid: Vec<Vec<Position>>,
-}
-
-impl Display for Grid {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for row in &self.grid {
-            for pos in row {
-                f.write_str(match pos {
-                    Position::Floor => ".",
-                    Position::Seat => "L",
-                    Position::Person => "#",
-                })?;
-            }
-            writeln!(f)?;
+impl Cell for Position {
+    fn from_char(c: char) -> Option<Self> {
+        match c {
+            '.' => Some(Position::Floor),
+            'L' => Some(Position::Seat),
+            '#' => Some(Position::Person),
+            _ => None,
         }
-        Ok(())
     }
-}
 
-impl Grid {
-    pub fn new(grid: Vec<Vec<Position>>) -> Self {
-        Self { grid }
+    fn to_char(&self) -> char {
+        match self {
+            Position::Floor => '.',
+            Position::Seat => 'L',
+            Position::Person => '#',
+        }
     }
+}
 
+impl Grid<Position> {
     pub fn step(
         &mut self,
-        count_fn: impl Fn(&Grid, usize, usize) -> usize,
+        count_fn: impl Fn(&Grid<Position>, Pos) -> usize,
         birth_range: RangeInclusive<usize>,
         death_range: RangeInclusive<usize>,
     ) -> bool {
         let mut changed = false;
         let old = self.clone();
-        for i in 0..old.grid.len() {
-            for j in 0..old.grid[i].len() {
-                self.grid[i][j] = match (old.grid[i][j], count_fn(&old, i, j)) {
-                    (Position::Seat, count) if birth_range.contains(&count) => {
-                        changed = true;
-                        Position::Person
-                    }
-                    (Position::Person, count) if death_range.contains(&count) => {
-                        changed = true;
-                        Position::Seat
-                    }
-                    (p, _) => p,
-                };
-            }
+        for (pos, &position) in old.positions() {
+            self[pos] = match (position, count_fn(&old, pos)) {
+                (Position::Seat, count) if birth_range.contains(&count) => {
+                    changed = true;
+                    Position::Person
+                }
+                (Position::Person, count) if death_range.contains(&count) => {
+                    changed = true;
+                    Position::Seat
+                }
+                (p, _) => p,
+            };
         }
         changed
     }
 
-    fn adjacent_people(&self, row: usize, col: usize) -> usize {
-        let mut count = 0;
-        for i in row.saturating_sub(1)..=usize::min(row + 1, self.grid.len() - 1) {
-            for j in col.saturating_sub(1)..=usize::min(col + 1, self.grid[row].len() - 1) {
-                if i == row && j == col {
-                    continue;
-                }
-                if self.grid[i][j] == Position::Person {
-                    count += 1;
-                }
-            }
-        }
-        count
+    fn adjacent_people(&self, pos: Pos) -> usize {
+        self.neighbours(pos, Connectivity::Eight)
+            .filter(|&neighbour| self[neighbour] == Position::Person)
+            .count()
     }
 
-    fn line_of_sight_adjacent(&self, row: usize, col: usize) -> usize {
-        let mut count = 0;
-        for y_dir in -1..=1 {
-            for x_dir in -1..=1 {
-                if x_dir == 0 && y_dir == 0 {
-                    continue;
-                }
-                let mut i = row as isize;
-                let mut j = col as isize;
-                loop {
-                    i += x_dir;
-                    j += y_dir;
-                    if !(0..self.grid.len() as isize).contains(&i)
-                        || !(0..self.grid[i as usize].len() as isize).contains(&j)
-                    {
-                        break;
-                    }
-                    match self.grid[i as usize][j as usize] {
-                        Position::Floor => continue,
-                        Position::Seat => break,
-                        Position::Person => {
-                            count += 1;
-                            break;
-                        }
-                    }
-                }
-            }
-        }
-        count
+    fn line_of_sight_adjacent(&self, pos: Pos) -> usize {
+        Connectivity::Eight
+            .offsets()
+            .iter()
+            .filter(|&&direction| {
+                self.ray(pos, direction)
+                    .map(|seen| self[seen])
+                    .find(|&seen| seen != Position::Floor)
+                    == Some(Position::Person)
+            })
+            .count()
     }
 }
 
 #[aoc_generator(day11)]
-pub fn input_generator(input: &str) -> Grid {
-    Grid::new(
-        input
-            .lines()
-            .map(|line| {
-                line.chars()
-                    .map(|c| match c {
-                        '.' => Position::Floor,
-                        'L' => Position::Seat,
-                        '#' => Position::Person,
-                        c => panic!("Unexpected character {}", c),
-                    })
-                    .collect()
-            })
-            .collect(),
-    )
+pub fn input_generator(input: &str) -> Result<Grid<Position>, ParseError> {
+    Grid::parse(11, input)
 }
 
 #[aoc(day11, part1)]
-pub fn day11_part1(input: &Grid) -> usize {
+pub fn day11_part1(input: &Grid<Position>) -> Result<usize, SolveError> {
     let mut grid = input.clone();
     while grid.step(Grid::adjacent_people, 0..=0, 4..=8) {}
-    grid.grid
-        .iter()
-        .flatten()
-        .filter(|&&pos| pos == Position::Person)
-        .count()
+    Ok(grid.iter().filter(|&&pos| pos == Position::Person).count())
 }
 
 #[aoc(day11, part2)]
-pub fn day11_part2(input: &Grid) -> usize {
+pub fn day11_part2(input: &Grid<Position>) -> Result<usize, SolveError> {
     let mut grid = input.clone();
     while grid.step(Grid::line_of_sight_adjacent, 0..=0, 5..=8) {}
-    grid.grid
-        .iter()
-        .flatten()
-        .filter(|&&pos| pos == Position::Person)
-        .count()
+    Ok(grid.iter().filter(|&&pos| pos == Position::Person).count())
 }
 
 #[cfg(test)]
@@ -168,8 +116,15 @@ mod tests {
 
     #[test]
     fn part1() {
-        let grid = input_generator(INPUT);
-        let occupied = day11_part1(&grid);
+        let grid = input_generator(INPUT).unwrap();
+        let occupied = day11_part1(&grid).unwrap();
         assert_eq!(37, occupied);
     }
+
+    #[test]
+    fn part2() {
+        let grid = input_generator(INPUT).unwrap();
+        let occupied = day11_part2(&grid).unwrap();
+        assert_eq!(26, occupied);
+    }
 }
@@ -1,5 +1,7 @@
 use aoc_runner_derive::*;
 
+use crate::{ParseError, SolveError};
+
 #[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq)]
 pub enum Move {
     North(i32),
@@ -31,91 +33,118 @@ impl Ship {
         self.position.0.abs() + self.position.1.abs()
     }
 
-    fn move_by(&mut self, movement: Move) {
+    fn move_by(&mut self, movement: Move) -> Result<(), SolveError> {
         match movement {
-            Move::North(dist) => self.move_heading(0, dist),
-            Move::South(dist) => self.move_heading(180, dist),
-            Move::East(dist) => self.move_heading(90, dist),
-            Move::West(dist) => self.move_heading(270, dist),
+            Move::North(dist) => self.move_heading(0, dist)?,
+            Move::South(dist) => self.move_heading(180, dist)?,
+            Move::East(dist) => self.move_heading(90, dist)?,
+            Move::West(dist) => self.move_heading(270, dist)?,
             Move::TurnLeft(degrees) => self.heading = (self.heading - degrees).rem_euclid(360),
             Move::TurnRight(degrees) => self.heading = (self.heading + degrees).rem_euclid(360),
-            Move::MoveForward(dist) => self.move_heading(self.heading, dist),
+            Move::MoveForward(dist) => self.move_heading(self.heading, dist)?,
         }
+        Ok(())
     }
 
     #[allow(clippy::zero_prefixed_literal)]
-    fn move_heading(&mut self, heading: i32, dist: i32) {
+    fn move_heading(&mut self, heading: i32, dist: i32) -> Result<(), SolveError> {
         match heading {
             000 => self.position.1 += dist,
             090 => self.position.0 += dist,
             180 => self.position.1 -= dist,
             270 => self.position.0 -= dist,
-            _ => panic!("Can't move with heading {}", heading),
+            _ => {
+                return Err(SolveError::InvalidInput(format!(
+                    "Can't move with heading {}",
+                    heading
+                )))
+            }
         }
+        Ok(())
     }
 
-    fn move_waypoint(&mut self, movement: Move) {
+    fn move_waypoint(&mut self, movement: Move) -> Result<(), SolveError> {
         match movement {
             Move::North(dist) => self.waypoint.1 += dist,
             Move::South(dist) => self.waypoint.1 -= dist,
             Move::East(dist) => self.waypoint.0 += dist,
             Move::West(dist) => self.waypoint.0 -= dist,
-            Move::TurnLeft(degrees) => self.rotate_waypoint(360 - degrees),
-            Move::TurnRight(degrees) => self.rotate_waypoint(degrees),
+            Move::TurnLeft(degrees) => self.rotate_waypoint(360 - degrees)?,
+            Move::TurnRight(degrees) => self.rotate_waypoint(degrees)?,
             Move::MoveForward(dist) => {
                 self.position.0 += dist * self.waypoint.0;
                 self.position.1 += dist * self.waypoint.1;
             }
         }
+        Ok(())
     }
 
     #[allow(clippy::zero_prefixed_literal)]
-    fn rotate_waypoint(&mut self, degrees: i32) {
+    fn rotate_waypoint(&mut self, degrees: i32) -> Result<(), SolveError> {
         self.waypoint = match degrees {
             000 => self.waypoint,
             090 => (self.waypoint.1, -self.waypoint.0),
             180 => (-self.waypoint.0, -self.waypoint.1),
             270 => (-self.waypoint.1, self.waypoint.0),
-            _ => panic!("Cannot rotate by {}", degrees),
-        }
+            _ => {
+                return Err(SolveError::InvalidInput(format!(
+                    "Cannot rotate by {}",
+                    degrees
+                )))
+            }
+        };
+        Ok(())
     }
 }
 
 #[aoc_generator(day12)]
-pub fn input_generator(input: &str) -> Vec<Move> {
+pub fn input_generator(input: &str) -> Result<Vec<Move>, ParseError> {
     input
         .lines()
         .map(|line| {
-            let (c, num) = line.split_at(1);
-            let num = num.parse().unwrap();
-            match c {
-                "N" => Move::North(num),
-                "S" => Move::South(num),
-                "E" => Move::East(num),
-                "W" => Move::West(num),
-                "L" => Move::TurnLeft(num),
-                "R" => Move::TurnRight(num),
-                "F" => Move::MoveForward(num),
-                _ => panic!("Unexpected movement command {} for {}", c, num),
-            }
+            let mut chars = line.chars();
+            let c = chars
+                .next()
+                .ok_or_else(|| ParseError::at(12, input, line, "Expected a movement command"))?;
+            let num = chars.as_str();
+            let num = num.parse().map_err(|e| {
+                ParseError::at(12, input, num, format!("Invalid distance {:?}: {}", num, e))
+            })?;
+            Ok(match c {
+                'N' => Move::North(num),
+                'S' => Move::South(num),
+                'E' => Move::East(num),
+                'W' => Move::West(num),
+                'L' => Move::TurnLeft(num),
+                'R' => Move::TurnRight(num),
+                'F' => Move::MoveForward(num),
+                _ => {
+                    return Err(ParseError::at(
+                        12,
+                        input,
+                        line,
+                        format!("Unexpected movement command {} for {}", c, num),
+                    ))
+                }
+            })
         })
         .collect()
 }
 
 #[aoc(day12, part1)]
-pub fn day12_part1(input: &[Move]) -> i32 {
+pub fn day12_part1(input: &[Move]) -> Result<i32, SolveError> {
     let mut ship = Ship::new();
     for &movement in input {
-        ship.move_by(movement);
+        ship.move_by(movement)?;
     }
-    ship.manhattan_distance()
+    Ok(ship.manhattan_distance())
 }
 
 #[aoc(day12, part2)]
-pub fn day12_part2(input: &[Move]) -> i32 {
+pub fn day12_part2(input: &[Move]) -> Result<i32, SolveError> {
     let mut ship = Ship::new();
     for &movement in input {
-        ship.move_waypoint(movement);
+        ship.move_waypoint(movement)?;
     }
-    ship.manhattan_distance()
+    Ok(ship.manhattan_distance())
 }
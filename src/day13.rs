@@ -1,47 +1,77 @@
 use aoc_runner_derive::*;
 
+use crate::{ParseError, SolveError};
+
 #[aoc_generator(day13)]
-pub fn input_generator(input: &str) -> (i64, Vec<Option<i64>>) {
+pub fn input_generator(input: &str) -> Result<(i64, Vec<Option<i64>>), ParseError> {
+    let end = &input[input.len()..];
     let mut lines = input.lines();
-    let earliest_time = lines.next().unwrap().parse().unwrap();
+    let earliest_time = lines
+        .next()
+        .ok_or_else(|| ParseError::at(13, input, end, "Missing earliest departure time"))?;
+    let earliest_time = earliest_time.parse().map_err(|e| {
+        ParseError::at(
+            13,
+            input,
+            earliest_time,
+            format!("Invalid departure time: {}", e),
+        )
+    })?;
     let bus_ids = lines
         .next()
-        .unwrap()
+        .ok_or_else(|| ParseError::at(13, input, end, "Missing bus IDs"))?
         .split(',')
-        .map(|id| id.parse().ok())
-        .collect();
-    (earliest_time, bus_ids)
+        .map(|id| match id {
+            "x" => Ok(None),
+            id => id.parse().map(Some).map_err(|e| {
+                ParseError::at(13, input, id, format!("Invalid bus ID {:?}: {}", id, e))
+            }),
+        })
+        .collect::<Result<_, _>>()?;
+    Ok((earliest_time, bus_ids))
 }
 
 #[aoc(day13, part1)]
-pub fn day13_part1((time, bus_ids): &(i64, Vec<Option<i64>>)) -> i64 {
-    let mut ids: Vec<_> = bus_ids
+pub fn day13_part1((time, bus_ids): &(i64, Vec<Option<i64>>)) -> Result<i64, SolveError> {
+    check_bus_ids(bus_ids)?;
+    bus_ids
         .iter()
         .filter_map(|p| *p)
         .map(|id| (id, id - time % id))
-        .collect();
-    ids.sort_unstable_by_key(|(_, time)| *time);
-    let (id, wait_time) = ids[0];
-    id * wait_time
+        .min_by_key(|(_, time)| *time)
+        .map(|(id, wait_time)| id * wait_time)
+        .ok_or_else(|| SolveError::NoSolution("No buses in service".to_owned()))
 }
 
 #[aoc(day13, part2)]
-pub fn day13_part2((_, constraints): &(i64, Vec<Option<i64>>)) -> i64 {
+pub fn day13_part2((_, constraints): &(i64, Vec<Option<i64>>)) -> Result<i64, SolveError> {
+    check_bus_ids(constraints)?;
     let constraints: Vec<_> = constraints
         .iter()
         .enumerate()
         .filter_map(|(i, &id)| id.map(|id| (i, id)))
         .collect();
     let product: i64 = constraints.iter().map(|&(_, id)| id).product();
-    constraints
-        .iter()
-        .map(|&(offset, id)| {
-            let multiplier = product / id;
-            let (inv, _) = extended_euclid(multiplier, id);
-            multiplier * (inv * -(offset as i64)).rem_euclid(id)
-        })
-        .sum::<i64>()
-        .rem_euclid(product)
+    let mut sum = 0;
+    for &(offset, id) in &constraints {
+        let multiplier = product / id;
+        let (inv, t) = extended_euclid(multiplier, id);
+        if multiplier * inv + id * t != 1 {
+            return Err(SolveError::InvalidInput(format!(
+                "Bus {} shares a factor with another bus",
+                id
+            )));
+        }
+        sum += multiplier * (inv * -(offset as i64)).rem_euclid(id);
+    }
+    Ok(sum.rem_euclid(product))
+}
+
+fn check_bus_ids(bus_ids: &[Option<i64>]) -> Result<(), SolveError> {
+    match bus_ids.iter().flatten().find(|&&id| id <= 0) {
+        Some(id) => Err(SolveError::InvalidInput(format!("Invalid bus ID {}", id))),
+        None => Ok(()),
+    }
 }
 
 #[allow(clippy::many_single_char_names)]
@@ -70,15 +100,15 @@ mod tests {
 
     #[test]
     fn part1() {
-        let input = input_generator(INPUT);
+        let input = input_generator(INPUT).unwrap();
         let res = day13_part1(&input);
-        assert_eq!(295, res);
+        assert_eq!(Ok(295), res);
     }
 
     #[test]
     fn part2() {
         let res = day13_part2(&(0, vec![Some(17), None, Some(13), Some(19)]));
-        assert_eq!(3417, res)
+        assert_eq!(Ok(3417), res)
     }
 
     #[test]
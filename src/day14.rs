@@ -2,25 +2,31 @@ use std::collections::HashMap;
 
 use aoc_runner_derive::*;
 
+use crate::{ParseError, SolveError};
+
 #[derive(Debug, Copy, Clone)]
 pub struct BitMask {
     mask: [Option<bool>; 36],
 }
 
 impl BitMask {
-    fn new(bitmask: &str) -> Self {
-        let bitmask = &bitmask[7..];
-        assert_eq!(36, bitmask.len());
+    fn new(bitmask: &str) -> Result<Self, String> {
+        let bitmask = bitmask
+            .strip_prefix("mask = ")
+            .ok_or_else(|| format!("Expected bitmask, got: {}", bitmask))?;
+        if bitmask.len() != 36 {
+            return Err(format!("Bitmask must be 36 bits long, got: {}", bitmask));
+        }
         let mut mask = [None; 36];
         for (i, &c) in bitmask.as_bytes().iter().rev().enumerate() {
             mask[i] = match c {
                 b'0' => Some(false),
                 b'1' => Some(true),
                 b'X' => None,
-                c => panic!("Unexpected character in bitmask: {}", c),
+                c => return Err(format!("Unexpected character in bitmask: {}", c as char)),
             };
         }
-        Self { mask }
+        Ok(Self { mask })
     }
 
     fn mask(self, mut value: u64) -> u64 {
@@ -103,23 +109,26 @@ pub struct Assignment {
 }
 
 impl Assignment {
-    fn new(assignment: &str) -> Self {
+    fn new(assignment: &str) -> Result<Self, String> {
         let address_start = assignment
             .find('[')
-            .unwrap_or_else(|| panic!("Couldn't find address start in: {}", assignment))
+            .ok_or_else(|| format!("Couldn't find address start in: {}", assignment))?
             + 1;
         let address_end = assignment
             .find(']')
-            .unwrap_or_else(|| panic!("Couldn't find address end in: {}", assignment));
-        let value_start = address_end + 4;
-        Self {
-            address: assignment[address_start..address_end]
+            .ok_or_else(|| format!("Couldn't find address end in: {}", assignment))?;
+        let value = assignment[address_end..]
+            .strip_prefix("] = ")
+            .ok_or_else(|| format!("Couldn't find value in: {}", assignment))?;
+        Ok(Self {
+            address: assignment
+                .get(address_start..address_end)
+                .and_then(|address| address.parse().ok())
+                .ok_or_else(|| format!("Address not a number in: {}", assignment))?,
+            value: value
                 .parse()
-                .unwrap_or_else(|_| panic!("Address not a number in: {}", assignment)),
-            value: assignment[value_start..]
-                .parse()
-                .unwrap_or_else(|_| panic!("Value not a number in {}", assignment)),
-        }
+                .map_err(|_| format!("Value not a number in {}", assignment))?,
+        })
     }
 }
 
@@ -130,25 +139,26 @@ pub enum Instruction {
 }
 
 #[aoc_generator(day14)]
-pub fn input_generator(input: &str) -> Vec<Instruction> {
+pub fn input_generator(input: &str) -> Result<Vec<Instruction>, ParseError> {
     input
         .lines()
         .map(|line| {
             if line.starts_with("mask") {
-                Instruction::BitMask(BitMask::new(line))
+                BitMask::new(line).map(Instruction::BitMask)
             } else if line.starts_with("mem") {
-                Instruction::Assignment(Assignment::new(line))
+                Assignment::new(line).map(Instruction::Assignment)
             } else {
-                panic!("Unexpected input line {}", line);
+                Err(format!("Unexpected input line {}", line))
             }
+            .map_err(|e| ParseError::at(14, input, line, e))
         })
         .collect()
 }
 
 #[aoc(day14, part1)]
-pub fn day14_part1(instructions: &[Instruction]) -> u64 {
+pub fn day14_part1(instructions: &[Instruction]) -> Result<u64, SolveError> {
     let mut memory: HashMap<u64, u64> = HashMap::new();
-    let mut current_mask = BitMask::new("mask = XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX");
+    let mut current_mask = BitMask { mask: [None; 36] };
     for instruction in instructions {
         match instruction {
             Instruction::BitMask(mask) => current_mask = *mask,
@@ -158,13 +168,13 @@ pub fn day14_part1(instructions: &[Instruction]) -> u64 {
             }
         }
     }
-    memory.values().sum()
+    Ok(memory.values().sum())
 }
 
 #[aoc(day14, part2)]
-pub fn day14_part2(instructions: &[Instruction]) -> u64 {
+pub fn day14_part2(instructions: &[Instruction]) -> Result<u64, SolveError> {
     let mut memory: HashMap<u64, u64> = HashMap::new();
-    let mut current_mask = BitMask::new("mask = XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX");
+    let mut current_mask = BitMask { mask: [None; 36] };
     for instruction in instructions {
         match instruction {
             Instruction::BitMask(mask) => current_mask = *mask,
@@ -175,7 +185,7 @@ pub fn day14_part2(instructions: &[Instruction]) -> u64 {
             }
         }
     }
-    memory.values().sum()
+    Ok(memory.values().sum())
 }
 
 #[cfg(test)]
@@ -184,7 +194,7 @@ mod tests {
 
     #[test]
     fn mask() {
-        let mask = BitMask::new("mask = XXXXXXXXXXXXXXXXXXXXXXXXXXXXX1XXXX0X");
+        let mask = BitMask::new("mask = XXXXXXXXXXXXXXXXXXXXXXXXXXXXX1XXXX0X").unwrap();
         assert_eq!(73, mask.mask(11));
         assert_eq!(101, mask.mask(101));
         assert_eq!(64, mask.mask(0));
@@ -192,7 +202,7 @@ mod tests {
 
     #[test]
     fn values() {
-        let mask = BitMask::new("mask = 000000000000000000000000000000X1001X");
+        let mask = BitMask::new("mask = 000000000000000000000000000000X1001X").unwrap();
         assert_eq!(
             vec![26, 27, 58, 59],
             (mask | 42).values().collect::<Vec<_>>()
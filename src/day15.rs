@@ -2,6 +2,8 @@ use std::collections::HashMap;
 
 use aoc_runner_derive::*;
 
+use crate::{ParseError, SolveError};
+
 #[derive(Debug, Clone)]
 struct MemoryGame {
     time: u64,
@@ -42,20 +44,26 @@ impl Iterator for MemoryGame {
 }
 
 #[aoc_generator(day15)]
-pub fn input_generator(input: &str) -> Vec<u64> {
-    input.split(',').map(|n| n.parse().unwrap()).collect()
+pub fn input_generator(input: &str) -> Result<Vec<u64>, ParseError> {
+    input
+        .split(',')
+        .map(|n| {
+            n.parse()
+                .map_err(|e| ParseError::at(15, input, n, format!("Invalid number {:?}: {}", n, e)))
+        })
+        .collect()
 }
 
 #[aoc(day15, part1)]
-pub fn day15_part1(input: &[u64]) -> u64 {
+pub fn day15_part1(input: &[u64]) -> Result<u64, SolveError> {
     let mut game = MemoryGame::new(input.to_vec());
-    game.nth(2020 - 1).unwrap()
+    Ok(game.nth(2020 - 1).unwrap())
 }
 
 #[aoc(day15, part2)]
-pub fn day15_part2(input: &[u64]) -> u64 {
+pub fn day15_part2(input: &[u64]) -> Result<u64, SolveError> {
     let mut game = MemoryGame::new(input.to_vec());
-    game.nth(30_000_000 - 1).unwrap()
+    Ok(game.nth(30_000_000 - 1).unwrap())
 }
 
 #[cfg(test)]
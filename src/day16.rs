@@ -2,6 +2,8 @@ use std::ops::RangeInclusive;
 
 use aoc_runner_derive::*;
 
+use crate::{ParseError, SolveError};
+
 #[derive(Debug)]
 pub struct Input {
     fields: Vec<(String, Vec<RangeInclusive<u64>>)>,
@@ -10,50 +12,70 @@ pub struct Input {
 }
 
 #[aoc_generator(day16)]
-pub fn input_generator(input: &str) -> Input {
+pub fn input_generator(input: &str) -> Result<Input, ParseError> {
+    let err = |part: &str, message: String| ParseError::at(16, input, part, message);
+    let end = &input[input.len()..];
+    let parse_num = |num: &str| {
+        num.parse()
+            .map_err(|e| err(num, format!("Invalid number {:?}: {}", num, e)))
+    };
+    let parse_ticket = |line: &str| line.split(',').map(parse_num).collect::<Result<_, _>>();
+    let expect_line = |lines: &mut std::str::Lines, expected: &str| match lines.next() {
+        Some(line) if line == expected => Ok(()),
+        Some(line) => Err(err(
+            line,
+            format!("Expected {:?}, got {:?}", expected, line),
+        )),
+        None => Err(err(
+            end,
+            format!("Expected {:?}, got end of input", expected),
+        )),
+    };
+
     let mut lines = input.lines();
     let fields = lines
         .by_ref()
         .take_while(|line| !line.is_empty())
         .map(|line| {
-            let (name, ranges) = line.split_once(':').unwrap();
+            let (name, ranges) = line
+                .split_once(':')
+                .ok_or_else(|| err(line, format!("Expected field definition, got {:?}", line)))?;
             let ranges = ranges
                 .split("or")
                 .map(|range| {
                     let range = range.trim();
-                    let (start, end) = range.split_once('-').unwrap();
-                    start.parse().unwrap()..=end.parse().unwrap()
+                    let (start, end) = range
+                        .split_once('-')
+                        .ok_or_else(|| err(range, format!("Expected range, got {:?}", range)))?;
+                    Ok(parse_num(start)?..=parse_num(end)?)
                 })
-                .collect();
-            (name.to_owned(), ranges)
+                .collect::<Result<_, _>>()?;
+            Ok((name.to_owned(), ranges))
         })
-        .collect();
+        .collect::<Result<_, _>>()?;
 
-    assert_eq!("your ticket:", lines.next().unwrap());
-    let ticket: Vec<u64> = lines
-        .next()
-        .unwrap()
-        .split(',')
-        .map(|num| num.parse().unwrap())
-        .collect();
+    expect_line(&mut lines, "your ticket:")?;
+    let ticket = parse_ticket(
+        lines
+            .next()
+            .ok_or_else(|| err(end, "Expected your ticket, got end of input".to_owned()))?,
+    )?;
 
-    let mut other_tickets = Vec::new();
-    assert_eq!("", lines.next().unwrap());
-    assert_eq!("nearby tickets:", lines.next().unwrap());
+    expect_line(&mut lines, "")?;
+    expect_line(&mut lines, "nearby tickets:")?;
 
-    other_tickets
-        .extend(lines.map(|line| line.split(',').map(|num| num.parse().unwrap()).collect()));
+    let other_tickets = lines.map(parse_ticket).collect::<Result<_, _>>()?;
 
-    Input {
+    Ok(Input {
         fields,
         ticket,
         other_tickets,
-    }
+    })
 }
 
 #[aoc(day16, part1)]
-pub fn day16_part1(input: &Input) -> u64 {
-    input
+pub fn day16_part1(input: &Input) -> Result<u64, SolveError> {
+    Ok(input
         .other_tickets
         .iter()
         .flatten()
@@ -65,11 +87,18 @@ pub fn day16_part1(input: &Input) -> u64 {
                 .flat_map(|(_, v)| v)
                 .any(|range| range.contains(value))
         })
-        .sum()
+        .sum())
 }
 
 #[aoc(day16, part2)]
-pub fn day16_part2(input: &Input) -> u64 {
+pub fn day16_part2(input: &Input) -> Result<u64, SolveError> {
+    if input.fields.len() != input.ticket.len() {
+        return Err(SolveError::InvalidInput(format!(
+            "{} fields but your ticket has {} values",
+            input.fields.len(),
+            input.ticket.len()
+        )));
+    }
     let valid_tickets =
         input
             .other_tickets
@@ -88,6 +117,13 @@ pub fn day16_part2(input: &Input) -> u64 {
         .map(|_| vec![true; input.fields.len()])
         .collect();
     for ticket in valid_tickets {
+        if ticket.len() != input.ticket.len() {
+            return Err(SolveError::InvalidInput(format!(
+                "Nearby ticket has {} values, expected {}",
+                ticket.len(),
+                input.ticket.len()
+            )));
+        }
         for (i, value) in ticket.iter().enumerate() {
             for (j, (_, field_ranges)) in input.fields.iter().enumerate() {
                 if !field_ranges.iter().any(|range| range.contains(value)) {
@@ -114,17 +150,23 @@ pub fn day16_part2(input: &Input) -> u64 {
                 }
                 only_option
             })
-            .unwrap();
+            .ok_or_else(|| {
+                if options.iter().any(|possibles| possibles.contains(&true)) {
+                    SolveError::Ambiguous("Fields can't be narrowed to one location".to_owned())
+                } else {
+                    SolveError::NoSolution("Some fields fit no location".to_owned())
+                }
+            })?;
         for option in options.iter_mut() {
             option[field] = false;
         }
         permutation[field] = Some(loc);
     }
-    input
+    Ok(input
         .fields
         .iter()
         .enumerate()
         .filter(|(_, (field, _))| field.starts_with("departure"))
         .map(|(field, _)| input.ticket[permutation[field].unwrap()])
-        .product()
+        .product())
 }
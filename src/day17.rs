@@ -1,254 +1,249 @@
-use std::{collections::HashSet, fmt, ops::Range};
+use std::{fmt, ops::RangeInclusive};
 
 use aoc_runner_derive::*;
-#[derive(Default, Clone)]
-pub struct ConwayCube {
-    grid: HashSet<(i32, i32, i32)>,
-    x_range: Range<i32>,
-    y_range: Range<i32>,
-    z_range: Range<i32>,
+
+use crate::{
+    automaton::{Automaton, MirroredAutomaton, Point, Rule},
+    grid::Grid,
+    ParseError, SolveError,
+};
+
+pub type ConwayCube = Automaton<Point<3>>;
+pub type ConwayCube4d = Automaton<Point<4>>;
+
+/// Places the initial slice at z = 0 (and w = 0 etc.), with y increasing up the page.
+pub fn pocket_dimension<const N: usize>(grid: &Grid<bool>) -> Automaton<Point<N>> {
+    let active = grid.rows().rev().enumerate().flat_map(|(y, row)| {
+        row.iter()
+            .enumerate()
+            .filter(|(_, &cell)| cell)
+            .map(move |(x, _)| {
+                let mut point = [0; N];
+                point[0] = x as i32;
+                point[1] = y as i32;
+                Point(point)
+            })
+    });
+    Automaton::new(Rule::conway(), active)
 }
 
-impl fmt::Debug for ConwayCube {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        for z in self.z_range.clone() {
-            writeln!(f, "z={}", z)?;
-            for y in self.y_range.clone().rev() {
-                for x in self.x_range.clone() {
-                    if self.grid.contains(&(x, y, z)) {
-                        write!(f, "#")?;
-                    } else {
-                        write!(f, ".")?;
-                    }
-                }
-                writeln!(f)?;
-            }
-        }
-        Ok(())
-    }
+/// Every axis past y is symmetric, as the cube starts in a single x-y slice.
+fn mirrored<const N: usize>(cube: &Automaton<Point<N>>) -> MirroredAutomaton<N> {
+    MirroredAutomaton::new(cube, 2).expect("Pocket dimensions start symmetric")
 }
 
-impl ConwayCube {
-    fn new(grid: &Vec<Vec<bool>>) -> Self {
-        let mut cube = Self::default();
-        for (y, row) in grid.iter().rev().enumerate() {
-            for (x, cell) in row.iter().enumerate() {
-                cube.set((x as i32, y as i32, 0), *cell);
-            }
-        }
-        cube
-    }
+/// The dimension counts `simulate` supports.
+pub const DIMENSIONS: RangeInclusive<usize> = 2..=8;
 
-    fn set(&mut self, pos: (i32, i32, i32), active: bool) {
-        if active {
-            self.grid.insert(pos);
-            let expand_range = |range: &mut Range<i32>, value: i32| {
-                if range.len() == 0 {
-                    *range = value..(value + 1);
-                } else if value < range.start {
-                    range.start = value;
-                } else if value >= range.end {
-                    range.end = value + 1
-                }
-            };
-            expand_range(&mut self.x_range, pos.0);
-            expand_range(&mut self.y_range, pos.1);
-            expand_range(&mut self.z_range, pos.2);
-        } else {
-            self.grid.remove(&pos);
+/// Runs the pocket dimension for `cycles` cycles, passing every generation (starting with the
+/// initial state) to `dump`, and returns the number of active cubes at the end.
+pub fn simulate(
+    grid: &Grid<bool>,
+    dimensions: usize,
+    cycles: usize,
+    dump: impl FnMut(usize, &dyn fmt::Debug),
+) -> Result<usize, SolveError> {
+    fn run<const N: usize>(
+        grid: &Grid<bool>,
+        cycles: usize,
+        mut dump: impl FnMut(usize, &dyn fmt::Debug),
+    ) -> usize {
+        let mut cube = mirrored(&pocket_dimension::<N>(grid));
+        for cycle in 0..cycles {
+            dump(cycle, &cube);
+            cube = cube.step();
         }
+        dump(cycles, &cube);
+        cube.len()
     }
-
-    fn step(&self) -> Self {
-        let mut new = self.clone();
-        for x in self.x_range.start - 1..self.x_range.end + 1 {
-            for y in self.y_range.start - 1..self.y_range.end + 1 {
-                for z in self.z_range.start - 1..self.z_range.end + 1 {
-                    let mut count = 0;
-                    for x_offset in -1..=1 {
-                        for y_offset in -1..=1 {
-                            for z_offset in -1..=1 {
-                                if x_offset == 0 && y_offset == 0 && z_offset == 0 {
-                                    continue;
-                                }
-                                if self
-                                    .grid
-                                    .contains(&(x + x_offset, y + y_offset, z + z_offset))
-                                {
-                                    count += 1;
-                                }
-                            }
-                        }
-                    }
-                    let activity = self.grid.contains(&(x, y, z));
-                    match (activity, count) {
-                        (true, 2) | (true, 3) => {}
-                        (true, _) => new.set((x, y, z), false),
-                        (false, 3) => new.set((x, y, z), true),
-                        (false, _) => {}
-                    }
-                }
-            }
+    Ok(match dimensions {
+        2 => run::<2>(grid, cycles, dump),
+        3 => run::<3>(grid, cycles, dump),
+        4 => run::<4>(grid, cycles, dump),
+        5 => run::<5>(grid, cycles, dump),
+        6 => run::<6>(grid, cycles, dump),
+        7 => run::<7>(grid, cycles, dump),
+        8 => run::<8>(grid, cycles, dump),
+        _ => {
+            return Err(SolveError::InvalidInput(format!(
+                "Can't simulate {} dimensions, expected {} to {}",
+                dimensions,
+                DIMENSIONS.start(),
+                DIMENSIONS.end()
+            )))
         }
-        new
-    }
+    })
 }
 
-#[derive(Default, Clone)]
-pub struct ConwayCube4d {
-    grid: HashSet<(i32, i32, i32, i32)>,
-    x_range: Range<i32>,
-    y_range: Range<i32>,
-    z_range: Range<i32>,
-    w_range: Range<i32>,
+#[aoc_generator(day17)]
+pub fn input_generator(input: &str) -> Result<Grid<bool>, ParseError> {
+    Grid::parse(17, input)
 }
 
-impl fmt::Debug for ConwayCube4d {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        for w in self.w_range.clone() {
-            for z in self.z_range.clone() {
-                writeln!(f, "z={}, w={}", z, w)?;
-                for y in self.y_range.clone().rev() {
-                    for x in self.x_range.clone() {
-                        if self.grid.contains(&(x, y, z, w)) {
-                            write!(f, "#")?;
-                        } else {
-                            write!(f, ".")?;
-                        }
-                    }
-                    writeln!(f)?;
-                }
-            }
-        }
-        Ok(())
-    }
+#[aoc(day17, part1)]
+pub fn day17_part1(input: &Grid<bool>) -> Result<usize, SolveError> {
+    simulate(input, 3, 6, |_, _| {})
 }
 
-impl ConwayCube4d {
-    fn new(grid: &Vec<Vec<bool>>) -> Self {
-        let mut cube = Self::default();
-        for (y, row) in grid.iter().rev().enumerate() {
-            for (x, cell) in row.iter().enumerate() {
-                cube.set((x as i32, y as i32, 0, 0), *cell);
-            }
-        }
-        cube
-    }
+#[aoc(day17, part2)]
+pub fn day17_part2(input: &Grid<bool>) -> Result<usize, SolveError> {
+    simulate(input, 4, 6, |_, _| {})
+}
 
-    fn set(&mut self, pos: (i32, i32, i32, i32), active: bool) {
-        if active {
-            self.grid.insert(pos);
-            let expand_range = |range: &mut Range<i32>, value: i32| {
-                if range.len() == 0 {
-                    *range = value..(value + 1);
-                } else if value < range.start {
-                    range.start = value;
-                } else if value >= range.end {
-                    range.end = value + 1
-                }
-            };
-            expand_range(&mut self.x_range, pos.0);
-            expand_range(&mut self.y_range, pos.1);
-            expand_range(&mut self.z_range, pos.2);
-            expand_range(&mut self.w_range, pos.3);
-        } else {
-            self.grid.remove(&pos);
-        }
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn simulate_dimensions() {
+        let grid = input_generator(".#.\n..#\n###").unwrap();
+        let mut dumped = Vec::new();
+        let count = simulate(&grid, 2, 4, |cycle, cube| {
+            dumped.push((cycle, format!("{:?}", cube)))
+        });
+        assert_eq!(Ok(5), count);
+        assert_eq!(5, dumped.len());
+        assert_eq!((0, ".#.\n..#\n###\n".to_owned()), dumped[0]);
+        assert_eq!(Ok(112), simulate(&grid, 3, 6, |_, _| {}));
+        assert!(simulate(&grid, 9, 6, |_, _| {}).is_err());
     }
 
-    fn step(&self) -> Self {
-        let mut new = self.clone();
-        for x in self.x_range.start - 1..self.x_range.end + 1 {
-            for y in self.y_range.start - 1..self.y_range.end + 1 {
-                for z in self.z_range.start - 1..self.z_range.end + 1 {
-                    for w in self.w_range.start - 1..self.w_range.end + 1 {
-                        let mut count = 0;
-                        for x_offset in -1..=1 {
-                            for y_offset in -1..=1 {
-                                for z_offset in -1..=1 {
-                                    for w_offset in -1..=1 {
-                                        if x_offset == 0
-                                            && y_offset == 0
-                                            && z_offset == 0
-                                            && w_offset == 0
-                                        {
-                                            continue;
-                                        }
-                                        if self.grid.contains(&(
-                                            x + x_offset,
-                                            y + y_offset,
-                                            z + z_offset,
-                                            w + w_offset,
-                                        )) {
-                                            count += 1;
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                        let activity = self.grid.contains(&(x, y, z, w));
-                        match (activity, count) {
-                            (true, 2) | (true, 3) => {}
-                            (true, _) => new.set((x, y, z, w), false),
-                            (false, 3) => new.set((x, y, z, w), true),
-                            (false, _) => {}
-                        }
+    const EXAMPLE: &str = ".#.\n..#\n###";
+
+    /// Reads the puzzle's pictures of each slice, whose top-left corner is at `(left, top)`. Every
+    /// slice is labelled with its coordinates after x and y.
+    fn cubes<const N: usize>(left: i32, top: i32, slices: &[(&[i32], &str)]) -> HashSet<Point<N>> {
+        let mut cubes = HashSet::new();
+        for (coords, picture) in slices {
+            for (row, line) in picture.lines().enumerate() {
+                for (column, c) in line.chars().enumerate() {
+                    if c == '#' {
+                        let mut point = [0; N];
+                        point[0] = left + column as i32;
+                        point[1] = top - row as i32;
+                        point[2..].copy_from_slice(coords);
+                        cubes.insert(Point(point));
                     }
                 }
             }
         }
-        new
+        cubes
     }
-}
 
-#[aoc_generator(day17)]
-pub fn input_generator(input: &str) -> Vec<Vec<bool>> {
-    input
-        .lines()
-        .map(|line| {
-            line.chars()
-                .map(|c| match c {
-                    '.' => false,
-                    '#' => true,
-                    c => panic!("Unexpected character {:?}", c),
-                })
-                .collect()
-        })
-        .collect()
-}
+    #[test]
+    fn example_3d() {
+        let cube: ConwayCube = pocket_dimension(&input_generator(EXAMPLE).unwrap());
+        let cycle1 = "#..\n..#\n.#.";
+        let cube = cube.step();
+        let expected = cubes(
+            0,
+            1,
+            &[(&[-1], cycle1), (&[0], "#.#\n.##\n.#."), (&[1], cycle1)],
+        );
+        assert_eq!(&expected, cube.active());
 
-#[aoc(day17, part1)]
-pub fn day17_part1(input: &Vec<Vec<bool>>) -> usize {
-    let mut cube = ConwayCube::new(&input);
-    for _ in 0..6 {
-        cube = cube.step();
-    }
-    cube.grid.len()
-}
+        let cycle2_outer = ".....\n.....\n..#..\n.....\n.....";
+        let cycle2_inner = "..#..\n.#..#\n....#\n.#...\n.....";
+        let cube = cube.step();
+        let expected = cubes(
+            -1,
+            2,
+            &[
+                (&[-2], cycle2_outer),
+                (&[-1], cycle2_inner),
+                (&[0], "##...\n##...\n#....\n....#\n.###."),
+                (&[1], cycle2_inner),
+                (&[2], cycle2_outer),
+            ],
+        );
+        assert_eq!(&expected, cube.active());
 
-#[aoc(day17, part2)]
-pub fn day17_part2(input: &Vec<Vec<bool>>) -> usize {
-    let mut cube = ConwayCube4d::new(&input);
-    for _ in 0..6 {
-        cube = cube.step();
+        let cycle3_outer = ".......\n.......\n..##...\n..###..\n.......\n.......\n.......";
+        let cycle3_inner = "..#....\n...#...\n#......\n.....##\n.#...#.\n..#.#..\n...#...";
+        let cube = cube.step();
+        let expected = cubes(
+            -2,
+            3,
+            &[
+                (&[-2], cycle3_outer),
+                (&[-1], cycle3_inner),
+                (
+                    &[0],
+                    "...#...\n.......\n#......\n.......\n.....##\n.##.#..\n...#...",
+                ),
+                (&[1], cycle3_inner),
+                (&[2], cycle3_outer),
+            ],
+        );
+        assert_eq!(&expected, cube.active());
+
+        assert_eq!(112, cube.run(3).len());
     }
-    cube.grid.len()
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn example_4d() {
+        let cube: ConwayCube4d = pocket_dimension(&input_generator(EXAMPLE).unwrap());
+        let cycle1 = "#..\n..#\n.#.";
+        let cube = cube.step();
+        let coords: Vec<[i32; 2]> = (-1..=1)
+            .flat_map(|w| (-1..=1).map(move |z| [z, w]))
+            .collect();
+        let slices: Vec<(&[i32], &str)> = coords
+            .iter()
+            .map(|coords| {
+                let picture = if coords == &[0, 0] {
+                    "#.#\n.##\n.#."
+                } else {
+                    cycle1
+                };
+                (&coords[..], picture)
+            })
+            .collect();
+        assert_eq!(cubes(0, 1, &slices), *cube.active());
+
+        let corner = ".....\n.....\n..#..\n.....\n.....";
+        let edge = "###..\n##.##\n#...#\n.#..#\n.###.";
+        let cube = cube.step();
+        let expected = cubes(
+            -1,
+            2,
+            &[
+                (&[-2, -2], corner),
+                (&[2, -2], corner),
+                (&[-2, 2], corner),
+                (&[2, 2], corner),
+                (&[0, -2], edge),
+                (&[0, 2], edge),
+                (&[-2, 0], edge),
+                (&[2, 0], edge),
+            ],
+        );
+        assert_eq!(&expected, cube.active());
+
+        assert_eq!(848, cube.run(4).len());
+    }
 
     #[test]
-    fn glider() {
-        let cube = ConwayCube::new(&vec![
-            vec![false, true, false],
-            vec![false, false, true],
-            vec![true, true, true],
-        ]);
-        println!("{:?}", cube);
-        println!("{:?}", cube.step());
-        println!("{:?}", cube.step().step());
-        assert!(false)
+    fn set_expands_ranges() {
+        let mut cube = ConwayCube::new(Rule::conway(), []);
+        assert_eq!(&[0..0, 0..0, 0..0], cube.bounds());
+        cube.set(Point([2, -1, 0]), true);
+        assert_eq!(&[2..3, -1..0, 0..1], cube.bounds());
+        cube.set(Point([-1, 3, 0]), true);
+        assert_eq!(&[-1..3, -1..4, 0..1], cube.bounds());
+        cube.set(Point([0, 0, -4]), true);
+        assert_eq!(&[-1..3, -1..4, -4..1], cube.bounds());
+        cube.set(Point([-1, 3, 0]), false);
+        cube.set(Point([9, 9, 9]), false);
+        assert_eq!(&[-1..3, -1..4, -4..1], cube.bounds());
+        assert_eq!(2, cube.len());
+
+        // Stepping only ever grows the ranges, even once the cubes have moved on.
+        let cube: ConwayCube4d = pocket_dimension(&input_generator(EXAMPLE).unwrap());
+        assert_eq!(&[0..3, 0..3, 0..1, 0..1], cube.bounds());
+        assert_eq!(&[0..3, -1..3, -1..2, -1..2], cube.step().bounds());
+        assert_eq!(&[-1..4, -2..3, -2..3, -2..3], cube.run(2).bounds());
     }
 }
@@ -2,6 +2,8 @@ use std::iter::Peekable;
 
 use aoc_runner_derive::*;
 
+use crate::{ParseError, SolveError};
+
 #[derive(Debug)]
 pub enum Expr {
     Sum(Box<Expr>, Box<Expr>),
@@ -33,7 +35,7 @@ impl Expr {
                 }
                 lhs
             }
-            digit if digit.is_digit(10) => Expr::Number(digit.to_digit(10).unwrap().into()),
+            digit if digit.is_ascii_digit() => Expr::Number(digit.to_digit(10).unwrap().into()),
             c => {
                 return Err(format!(
                     "Unexpected token at start of expression, got {:?}",
@@ -78,12 +80,26 @@ impl Expr {
 }
 
 #[aoc_generator(day18)]
-pub fn input_generator(input: &str) -> Vec<String> {
-    input.lines().map(ToOwned::to_owned).collect()
+pub fn input_generator(input: &str) -> Result<Vec<String>, ParseError> {
+    let precedence = |op| {
+        Some(match op {
+            '+' | '*' => (1, 2),
+            _ => return None,
+        })
+    };
+
+    input
+        .lines()
+        .map(|line| {
+            Expr::parse(line, &precedence)
+                .map(|_| line.to_owned())
+                .map_err(|e| ParseError::at(18, input, line, e))
+        })
+        .collect()
 }
 
 #[aoc(day18, part1)]
-pub fn day18_part1(input: &[String]) -> i64 {
+pub fn day18_part1(input: &[String]) -> Result<i64, SolveError> {
     let precedence = |op| {
         Some(match op {
             '+' | '*' => (1, 2),
@@ -93,12 +109,13 @@ pub fn day18_part1(input: &[String]) -> i64 {
 
     input
         .iter()
-        .map(|s| Expr::parse(s, &precedence).unwrap().evaluate())
-        .sum()
+        .map(|s| Expr::parse(s, &precedence).map(|expr| expr.evaluate()))
+        .sum::<Result<_, _>>()
+        .map_err(SolveError::InvalidInput)
 }
 
 #[aoc(day18, part2)]
-pub fn day18_part2(input: &[String]) -> i64 {
+pub fn day18_part2(input: &[String]) -> Result<i64, SolveError> {
     let precedence = |op| {
         Some(match op {
             '+' => (3, 4),
@@ -109,8 +126,9 @@ pub fn day18_part2(input: &[String]) -> i64 {
 
     input
         .iter()
-        .map(|s| Expr::parse(s, &precedence).unwrap().evaluate())
-        .sum()
+        .map(|s| Expr::parse(s, &precedence).map(|expr| expr.evaluate()))
+        .sum::<Result<_, _>>()
+        .map_err(SolveError::InvalidInput)
 }
 
 #[cfg(test)]
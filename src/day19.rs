@@ -2,12 +2,31 @@ use std::{iter::FromIterator, str::FromStr};
 
 use aoc_runner_derive::*;
 
+use crate::{ParseError, SolveError};
+
 #[derive(Debug, Clone)]
 pub struct RuleSet {
     pub rules: Vec<Rule>,
 }
 
 impl RuleSet {
+    fn check(&self) -> Result<(), SolveError> {
+        if self.rules.is_empty() {
+            return Err(SolveError::InvalidInput("No rules".to_owned()));
+        }
+        for rule in &self.rules {
+            if let Rule::Alternation(alts) = rule {
+                if let Some(&missing) = alts.iter().flatten().find(|&&i| i >= self.rules.len()) {
+                    return Err(SolveError::InvalidInput(format!(
+                        "Rule {} is not defined",
+                        missing
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
     pub fn matches(&self, input: &str) -> bool {
         let output = self.matches_rule(input, 0);
         !output.is_empty() && output.iter().any(|s| s.is_empty())
@@ -76,37 +95,53 @@ impl FromStr for Rule {
 }
 
 #[aoc_generator(day19)]
-pub fn input_generator(input: &str) -> (RuleSet, Vec<String>) {
+pub fn input_generator(input: &str) -> Result<(RuleSet, Vec<String>), ParseError> {
     let mut lines = input.lines();
     let rule_set = lines
         .by_ref()
         .take_while(|line| !line.is_empty())
         .map(|line| {
-            let (index, rule) = line.split_once(':').unwrap();
-            (index.parse().unwrap(), rule.trim().parse().unwrap())
+            let (index, rule) = line
+                .split_once(':')
+                .ok_or_else(|| ParseError::at(19, input, line, "Expected rule number"))?;
+            let index = index.parse().map_err(|e| {
+                ParseError::at(19, input, index, format!("Invalid rule number: {}", e))
+            })?;
+            let rule = rule.trim();
+            let rule = rule
+                .parse()
+                .map_err(|e| ParseError::at(19, input, rule, e))?;
+            Ok((index, rule))
         })
-        .collect();
+        .collect::<Result<_, _>>()?;
     let images = lines.map(ToOwned::to_owned).collect();
-    (rule_set, images)
+    Ok((rule_set, images))
 }
 
 #[aoc(day19, part1)]
-pub fn day19_part1((rule_set, images): &(RuleSet, Vec<String>)) -> usize {
-    images
+pub fn day19_part1((rule_set, images): &(RuleSet, Vec<String>)) -> Result<usize, SolveError> {
+    rule_set.check()?;
+    Ok(images
         .iter()
         .filter(|image| rule_set.matches(image))
-        .count()
+        .count())
 }
 
 #[aoc(day19, part2)]
-pub fn day19_part2((rule_set, images): &(RuleSet, Vec<String>)) -> usize {
+pub fn day19_part2((rule_set, images): &(RuleSet, Vec<String>)) -> Result<usize, SolveError> {
+    if rule_set.rules.len() <= 42 {
+        return Err(SolveError::InvalidInput(
+            "Rules 8, 11, 31 and 42 must be defined".to_owned(),
+        ));
+    }
     let mut rule_set = rule_set.clone();
     rule_set.rules[8] = Rule::Alternation(vec![vec![42], vec![42, 8]]);
     rule_set.rules[11] = Rule::Alternation(vec![vec![42, 31], vec![42, 11, 31]]);
-    images
+    rule_set.check()?;
+    Ok(images
         .iter()
         .filter(|image| rule_set.matches(image))
-        .count()
+        .count())
 }
 
 #[cfg(test)]
@@ -7,6 +7,11 @@ use std::{
 
 use aoc_runner_derive::*;
 
+use crate::{
+    grid::{Grid, Transform},
+    normalise_line_endings, ParseError, SolveError,
+};
+
 type Id = u64;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -63,6 +68,21 @@ impl Orientation {
     }
 }
 
+impl Orientation {
+    pub fn transform(self) -> Transform {
+        match self {
+            Orientation::None => Transform::Identity,
+            Orientation::Rotate90 => Transform::Rotate90,
+            Orientation::Rotate180 => Transform::Rotate180,
+            Orientation::Rotate270 => Transform::Rotate270,
+            Orientation::Flip0 => Transform::FlipHorizontal,
+            Orientation::Flip90 => Transform::AntiTranspose,
+            Orientation::Flip180 => Transform::FlipVertical,
+            Orientation::Flip270 => Transform::Transpose,
+        }
+    }
+}
+
 impl TryFrom<usize> for Orientation {
     type Error = ();
 
@@ -138,24 +158,15 @@ impl Image {
         }
     }
 
-    pub fn oriented(&self, orientation: Orientation) -> [[bool; 8]; 8] {
-        let mut oriented = [[false; 8]; 8];
-        for (i, row) in oriented.iter_mut().enumerate() {
-            for (j, cell) in row.iter_mut().enumerate() {
-                let (x, y) = match orientation {
-                    Orientation::None => (i, j),
-                    Orientation::Rotate90 => (7 - j, i),
-                    Orientation::Rotate180 => (7 - i, 7 - j),
-                    Orientation::Rotate270 => (j, 7 - i),
-                    Orientation::Flip0 => (i, 7 - j),
-                    Orientation::Flip90 => (7 - j, 7 - i),
-                    Orientation::Flip180 => (7 - i, j),
-                    Orientation::Flip270 => (j, i),
-                };
-                *cell = self.data[x + 1][y + 1];
-            }
-        }
-        oriented
+    pub fn grid(&self) -> Grid<bool> {
+        Grid::from_fn(10, 10, |(row, col)| self.data[row][col])
+    }
+
+    /// The image with its border removed, in the given orientation.
+    pub fn oriented(&self, orientation: Orientation) -> Grid<bool> {
+        let grid = self.grid();
+        let view = grid.view(orientation.transform());
+        Grid::from_fn(8, 8, |(row, col)| view[(row + 1, col + 1)])
     }
 }
 
@@ -164,25 +175,13 @@ pub struct Oriented {
     orientation: Orientation,
 }
 
-impl<'a> fmt::Debug for Oriented {
+impl fmt::Debug for Oriented {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        for i in 0..10 {
-            for j in 0..10 {
-                let (row, col) = match self.orientation {
-                    Orientation::None => (i, j),
-                    Orientation::Rotate90 => (9 - j, i),
-                    Orientation::Rotate180 => (9 - i, 9 - j),
-                    Orientation::Rotate270 => (j, 9 - i),
-                    Orientation::Flip0 => (i, 9 - j),
-                    Orientation::Flip90 => (9 - j, 9 - i),
-                    Orientation::Flip180 => (9 - i, j),
-                    Orientation::Flip270 => (j, i),
-                };
-                f.write_str(if self.image.data[row][col] { "#" } else { "." })?;
-            }
-            f.write_str("\n")?;
-        }
-        Ok(())
+        write!(
+            f,
+            "{}",
+            self.image.grid().view(self.orientation.transform())
+        )
     }
 }
 
@@ -226,37 +225,37 @@ impl FromIterator<Image> for Images {
 }
 
 #[aoc_generator(day20)]
-pub fn input_generator(input: &str) -> Images {
+pub fn input_generator(input: &str) -> Result<Images, ParseError> {
+    let input = normalise_line_endings(input);
+    let input = &*input;
     input
         .split("\n\n")
         .map(|s| {
+            let err = |part: &str, message: String| ParseError::at(20, input, part, message);
             let mut lines = s.lines();
-            let id = lines
-                .next()
-                .unwrap()
+            let header = lines.next().unwrap_or(s);
+            let id = header
                 .strip_prefix("Tile ")
-                .unwrap()
-                .strip_suffix(":")
-                .unwrap()
+                .and_then(|id| id.strip_suffix(':'))
+                .ok_or_else(|| err(header, format!("Expected tile header, got {:?}", header)))?;
+            let id = id
                 .parse()
-                .unwrap();
+                .map_err(|e| err(id, format!("Invalid tile ID {:?}: {}", id, e)))?;
+            let grid = Grid::<bool>::parse_lines(20, input, lines)?;
+            if (grid.width(), grid.height()) != (10, 10) {
+                return Err(err(header, format!("Tile {} is not 10x10", id)));
+            }
             let mut image = [[false; 10]; 10];
-            for (row, line) in lines.enumerate() {
-                for (col, pixel) in line.chars().enumerate() {
-                    image[row][col] = match pixel {
-                        '#' => true,
-                        '.' => false,
-                        _ => panic!("Unexpected character"),
-                    };
-                }
+            for ((row, col), &pixel) in grid.positions() {
+                image[row][col] = pixel;
             }
-            Image::new(id, image)
+            Ok(Image::new(id, image))
         })
         .collect()
 }
 
 #[aoc(day20, part1)]
-pub fn day20_part1(input: &Images) -> u64 {
+pub fn day20_part1(input: &Images) -> Result<u64, SolveError> {
     let corners: Vec<_> = input
         .images()
         .filter(|image| {
@@ -268,12 +267,17 @@ pub fn day20_part1(input: &Images) -> u64 {
                 == 12
         })
         .collect();
-    assert_eq!(4, corners.len());
-    corners.into_iter().map(|image| image.id).product()
+    if corners.len() != 4 {
+        return Err(SolveError::InvalidInput(format!(
+            "Expected 4 corner tiles, found {}",
+            corners.len()
+        )));
+    }
+    Ok(corners.into_iter().map(|image| image.id).product())
 }
 
 #[aoc(day20, part2)]
-pub fn day20_part2(input: &Images) -> usize {
+pub fn day20_part2(input: &Images) -> Result<usize, SolveError> {
     let corner = input
         .images()
         .find(|image| {
@@ -284,7 +288,7 @@ pub fn day20_part2(input: &Images) -> usize {
                 .count()
                 == 12
         })
-        .unwrap();
+        .ok_or_else(|| SolveError::InvalidInput("No corner tiles".to_owned()))?;
     let edges: Vec<usize> = corner.edges[..4]
         .iter()
         .enumerate()
@@ -296,9 +300,21 @@ pub fn day20_part2(input: &Images) -> usize {
         [1, 2] => Orientation::Rotate270,
         [2, 3] => Orientation::None,
         [0, 3] => Orientation::Rotate90,
-        edges => panic!("Cannot orient to {:?}", edges),
+        edges => {
+            return Err(SolveError::InvalidInput(format!(
+                "Cannot orient to {:?}",
+                edges
+            )))
+        }
     };
     let edge_length = (input.images().count() as f64).sqrt() as usize;
+    if edge_length * edge_length != input.images().count() {
+        return Err(SolveError::InvalidInput(format!(
+            "{} tiles can't form a square",
+            input.images().count()
+        )));
+    }
+    let no_match = |id| SolveError::NoSolution(format!("No tile matches the edge of tile {}", id));
     let mut puzzle = vec![vec![None; edge_length]; edge_length];
     puzzle[0][0] = Some((corner.id, orientation));
     for row in 0..edge_length {
@@ -309,7 +325,7 @@ pub fn day20_part2(input: &Images) -> usize {
                 .images_by_edge(edge)
                 .iter()
                 .find(|(id, _)| *id != prev_piece)
-                .unwrap();
+                .ok_or_else(|| no_match(prev_piece))?;
             puzzle[row][0] = Some((*piece, *orientation));
         }
         for col in 1..edge_length {
@@ -319,28 +335,24 @@ pub fn day20_part2(input: &Images) -> usize {
                 .images_by_edge(edge)
                 .iter()
                 .find(|(id, _)| *id != prev_piece)
-                .unwrap();
+                .ok_or_else(|| no_match(prev_piece))?;
             puzzle[row][col] = Some((*piece, orientation.flip_270()));
         }
     }
-    let mut picture: Vec<Vec<_>> = Vec::new();
-    for row in puzzle {
-        let row: Vec<_> = row
-            .into_iter()
-            .map(|image| {
-                let (id, orientation) = image.unwrap();
-                input.image_by_id(id).oriented(orientation)
-            })
-            .collect();
-        for i in 0..8 {
-            picture.push(
-                row.iter()
-                    .flat_map(|data| data[i].iter().copied())
-                    .collect(),
-            );
-        }
-    }
-    let picture_size = picture.len();
+    let tiles: Vec<Vec<_>> = puzzle
+        .into_iter()
+        .map(|row| {
+            row.into_iter()
+                .map(|image| {
+                    let (id, orientation) = image.unwrap();
+                    input.image_by_id(id).oriented(orientation)
+                })
+                .collect()
+        })
+        .collect();
+    let picture = Grid::from_fn(edge_length * 8, edge_length * 8, |(row, col)| {
+        tiles[row / 8][col / 8][(row % 8, col % 8)]
+    });
     let dragon: [Vec<bool>; 3] = [
         b"                  # "
             .iter()
@@ -360,40 +372,29 @@ pub fn day20_part2(input: &Images) -> usize {
         .enumerate()
         .flat_map(|(x, row)| row.iter().enumerate().map(move |(y, &b)| (x, y, b)))
         .collect();
-    let indexors: [Box<dyn Fn(usize, usize) -> (usize, usize)>; 8] = [
-        Box::new(|i, j| (i, j)),
-        Box::new(|i, j| (j, i)),
-        Box::new(|i, j| (i, picture_size - 1 - j)),
-        Box::new(|i, j| (j, picture_size - 1 - i)),
-        Box::new(|i, j| (picture_size - 1 - i, j)),
-        Box::new(|i, j| (picture_size - 1 - j, i)),
-        Box::new(|i, j| (picture_size - 1 - i, picture_size - 1 - j)),
-        Box::new(|i, j| (picture_size - 1 - j, picture_size - 1 - i)),
-    ];
-    for indexor in &indexors {
+    for &transform in &Transform::ALL {
+        let mut picture = picture.view(transform).to_grid();
         let mut found_dragon = false;
-        for i in 0..=picture_size - 3 {
-            for j in 0..=picture_size - 20 {
-                let is_dragon = dragon.iter().all(|&(x, y, b)| {
-                    let (p, q) = indexor(i + x, j + y);
-                    !b || picture[p][q]
-                });
+        for i in 0..=picture.height().saturating_sub(3) {
+            for j in 0..=picture.width().saturating_sub(20) {
+                let is_dragon = dragon
+                    .iter()
+                    .all(|&(x, y, b)| !b || picture.get((i + x, j + y)) == Some(&true));
                 if is_dragon {
                     found_dragon = true;
                     for &(x, y, b) in &dragon {
                         if b {
-                            let (p, q) = indexor(i + x, j + y);
-                            picture[p][q] = false;
+                            picture[(i + x, j + y)] = false;
                         }
                     }
                 }
             }
         }
         if found_dragon {
-            return picture.into_iter().flatten().filter(|&b| b).count();
+            return Ok(picture.iter().filter(|&&b| b).count());
         }
     }
-    panic!("No dragons!");
+    Err(SolveError::NoSolution("No dragons!".to_owned()))
 }
 
 #[cfg(test)]
@@ -512,7 +513,7 @@ mod tests {
 
     #[test]
     fn parser() {
-        let images = input_generator(TEST_INPUT);
+        let images = input_generator(TEST_INPUT).unwrap();
         assert_eq!(
             "\
             #.#.#####.\n\
@@ -538,7 +539,7 @@ mod tests {
 
     #[test]
     fn print_rotated() {
-        let images = input_generator(TEST_INPUT);
+        let images = input_generator(TEST_INPUT).unwrap();
         assert_eq!(
             "\
             ...#.##..#\n\
@@ -564,7 +565,14 @@ mod tests {
 
     #[test]
     fn dragons() {
-        let images = input_generator(TEST_INPUT);
-        assert_eq!(273, day20_part2(&images));
+        let images = input_generator(TEST_INPUT).unwrap();
+        assert_eq!(Ok(273), day20_part2(&images));
+    }
+
+    #[test]
+    fn crlf_input() {
+        let images = input_generator(&TEST_INPUT.replace('\n', "\r\n")).unwrap();
+        assert_eq!(Ok(20899048083289), day20_part1(&images));
+        assert_eq!(Ok(273), day20_part2(&images));
     }
 }
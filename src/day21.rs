@@ -2,6 +2,8 @@ use std::collections::{BTreeMap, HashMap, HashSet};
 
 use aoc_runner_derive::*;
 
+use crate::{ParseError, SolveError};
+
 #[derive(Debug)]
 pub struct Recipe {
     ingredients: HashSet<String>,
@@ -9,11 +11,13 @@ pub struct Recipe {
 }
 
 #[aoc_generator(day21)]
-pub fn input_generator(input: &str) -> Vec<Recipe> {
+pub fn input_generator(input: &str) -> Result<Vec<Recipe>, ParseError> {
     input
         .lines()
         .map(|line| {
-            let (ingredients, allergens) = line.split_once("(contains").unwrap();
+            let (ingredients, allergens) = line
+                .split_once("(contains")
+                .ok_or_else(|| ParseError::at(21, input, line, "Expected allergen list"))?;
             let ingredients = ingredients
                 .trim()
                 .split(' ')
@@ -22,20 +26,22 @@ pub fn input_generator(input: &str) -> Vec<Recipe> {
             let allergens = allergens
                 .trim()
                 .strip_suffix(')')
-                .unwrap()
+                .ok_or_else(|| {
+                    ParseError::at(21, input, allergens, "Expected ')' after allergen list")
+                })?
                 .split(", ")
                 .map(ToOwned::to_owned)
                 .collect();
-            Recipe {
+            Ok(Recipe {
                 ingredients,
                 allergens,
-            }
+            })
         })
         .collect()
 }
 
 #[aoc(day21, part1)]
-pub fn day21_part1(input: &[Recipe]) -> usize {
+pub fn day21_part1(input: &[Recipe]) -> Result<usize, SolveError> {
     let mut allergen_potentials: HashMap<String, HashSet<String>> = HashMap::new();
     for recipe in input {
         for allergen in &recipe.allergens {
@@ -52,15 +58,15 @@ pub fn day21_part1(input: &[Recipe]) -> usize {
         .fold(HashSet::new(), |acc, ingredients| {
             acc.union(ingredients).cloned().collect()
         });
-    input
+    Ok(input
         .iter()
         .flat_map(|recipe| &recipe.ingredients)
         .filter(|ingredient| !maybe_allergenic.contains(ingredient.as_str()))
-        .count()
+        .count())
 }
 
 #[aoc(day21, part2)]
-pub fn day21_part2(input: &[Recipe]) -> String {
+pub fn day21_part2(input: &[Recipe]) -> Result<String, SolveError> {
     let mut allergen_potentials: HashMap<String, HashSet<String>> = HashMap::new();
     for recipe in input {
         for allergen in &recipe.allergens {
@@ -83,11 +89,22 @@ pub fn day21_part2(input: &[Recipe]) -> String {
                     None
                 }
             })
-            .unwrap();
+            .ok_or_else(|| {
+                if allergen_potentials
+                    .values()
+                    .any(|ingredients| ingredients.len() > 1)
+                {
+                    SolveError::Ambiguous(
+                        "Allergens can't be narrowed to one ingredient".to_owned(),
+                    )
+                } else {
+                    SolveError::NoSolution("Some allergens are in no ingredient".to_owned())
+                }
+            })?;
         for ingredients in allergen_potentials.values_mut() {
             ingredients.remove(&ingredient);
         }
         allergens.insert(allergen, ingredient);
     }
-    allergens.values().cloned().collect::<Vec<_>>().join(",")
+    Ok(allergens.values().cloned().collect::<Vec<_>>().join(","))
 }
@@ -5,6 +5,8 @@ use std::{
 
 use aoc_runner_derive::*;
 
+use crate::{normalise_line_endings, ParseError, SolveError};
+
 #[derive(Debug, Clone)]
 pub struct Combat {
     player1: VecDeque<u32>,
@@ -101,41 +103,45 @@ impl Combat {
 }
 
 #[aoc_generator(day22)]
-pub fn input_generator(input: &str) -> Combat {
-    let (player1, player2) = input.split_once("\n\n").unwrap();
-    let player1 = player1
-        .trim()
-        .lines()
-        .skip(1)
-        .map(FromStr::from_str)
-        .collect::<Result<_, _>>()
-        .unwrap();
-    let player2 = player2
-        .trim()
-        .lines()
-        .skip(1)
-        .map(FromStr::from_str)
-        .collect::<Result<_, _>>()
-        .unwrap();
-    Combat { player1, player2 }
+pub fn input_generator(input: &str) -> Result<Combat, ParseError> {
+    let input = normalise_line_endings(input);
+    let input = &*input;
+    let (player1, player2) = input
+        .split_once("\n\n")
+        .ok_or_else(|| ParseError::at(22, input, input, "Expected two decks"))?;
+    let parse_deck = |deck: &str| {
+        deck.trim()
+            .lines()
+            .skip(1)
+            .map(|card| {
+                FromStr::from_str(card).map_err(|e| {
+                    ParseError::at(22, input, card, format!("Invalid card {:?}: {}", card, e))
+                })
+            })
+            .collect::<Result<_, _>>()
+    };
+    Ok(Combat {
+        player1: parse_deck(player1)?,
+        player2: parse_deck(player2)?,
+    })
 }
 
 #[aoc(day22, part1)]
-pub fn day22_part1(input: &Combat) -> u32 {
+pub fn day22_part1(input: &Combat) -> Result<u32, SolveError> {
     let mut combat = input.clone();
     let winner = loop {
         if let Some(winner) = combat.round() {
             break winner;
         }
     };
-    combat.score(winner)
+    Ok(combat.score(winner))
 }
 
 #[aoc(day22, part2)]
-pub fn day22_part2(input: &Combat) -> u32 {
+pub fn day22_part2(input: &Combat) -> Result<u32, SolveError> {
     let mut combat = input.clone();
     let winner = combat.recursive_game();
-    combat.score(winner)
+    Ok(combat.score(winner))
 }
 
 #[cfg(test)]
@@ -160,13 +166,20 @@ mod tests {
 
     #[test]
     fn part1() {
-        let input = input_generator(INPUT);
-        assert_eq!(306, day22_part1(&input));
+        let input = input_generator(INPUT).unwrap();
+        assert_eq!(Ok(306), day22_part1(&input));
     }
 
     #[test]
     fn part2() {
-        let input = input_generator(INPUT);
-        assert_eq!(291, day22_part2(&input));
+        let input = input_generator(INPUT).unwrap();
+        assert_eq!(Ok(291), day22_part2(&input));
+    }
+
+    #[test]
+    fn crlf_input() {
+        let input = input_generator(&INPUT.replace('\n', "\r\n")).unwrap();
+        assert_eq!(Ok(306), day22_part1(&input));
+        assert_eq!(Ok(291), day22_part2(&input));
     }
 }
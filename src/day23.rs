@@ -2,6 +2,8 @@ use std::fmt::{self, Write as _};
 
 use aoc_runner_derive::*;
 
+use crate::{ParseError, SolveError};
+
 #[derive(Debug, Clone)]
 pub struct CupCircle {
     next_cup: Vec<usize>,
@@ -21,6 +23,29 @@ impl fmt::Display for CupCircle {
 }
 
 impl CupCircle {
+    fn check(&self) -> Result<(), SolveError> {
+        let cups = self.next_cup.len().saturating_sub(1);
+        if cups < 4 {
+            return Err(SolveError::InvalidInput(format!(
+                "Need at least 4 cups, got {}",
+                cups
+            )));
+        }
+        let mut seen = vec![false; self.next_cup.len()];
+        let mut cup = self.current_cup;
+        for _ in 0..cups {
+            if cup == 0 || seen[cup] {
+                return Err(SolveError::InvalidInput(format!(
+                    "Cups must be labelled 1 to {}",
+                    cups
+                )));
+            }
+            seen[cup] = true;
+            cup = self.next_cup[cup];
+        }
+        Ok(())
+    }
+
     pub fn round(&mut self) {
         let max_label = self.next_cup.len() - 1;
         let next_one = self.next_cup[self.current_cup];
@@ -44,29 +69,52 @@ impl CupCircle {
 }
 
 #[aoc_generator(day23)]
-pub fn input_generator(input: &str) -> CupCircle {
+pub fn input_generator(input: &str) -> Result<CupCircle, ParseError> {
+    let cups = input
+        .char_indices()
+        .map(|(i, c)| {
+            c.to_digit(10).map(|cup| cup as usize).ok_or_else(|| {
+                ParseError::at(
+                    23,
+                    input,
+                    &input[i..],
+                    format!("Expected a cup label, got {:?}", c),
+                )
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let (&first, &last) = match (cups.first(), cups.last()) {
+        (Some(first), Some(last)) => (first, last),
+        _ => {
+            return Err(ParseError::at(
+                23,
+                input,
+                input,
+                "Expected at least one cup",
+            ))
+        }
+    };
     let mut next_cup = Vec::new();
-    for &[cup, next] in input.as_bytes().array_windows() {
-        let cup = (cup as char).to_digit(10).unwrap() as usize;
-        let next = (next as char).to_digit(10).unwrap() as usize;
-        next_cup.extend(std::iter::repeat(0).take((cup + 1).saturating_sub(next_cup.len())));
+    for pair in cups.windows(2) {
+        let (cup, next) = (pair[0], pair[1]);
+        if next_cup.len() <= cup {
+            next_cup.resize(cup + 1, 0);
+        }
         next_cup[cup] = next;
     }
-    let first = (*input.as_bytes().first().unwrap() as char)
-        .to_digit(10)
-        .unwrap() as usize;
-    let last = (*input.as_bytes().last().unwrap() as char)
-        .to_digit(10)
-        .unwrap() as usize;
+    if next_cup.len() <= last {
+        next_cup.resize(last + 1, 0);
+    }
     next_cup[last] = first;
-    CupCircle {
+    Ok(CupCircle {
         next_cup,
         current_cup: first,
-    }
+    })
 }
 
 #[aoc(day23, part1)]
-pub fn day23_part1(input: &CupCircle) -> String {
+pub fn day23_part1(input: &CupCircle) -> Result<String, SolveError> {
+    input.check()?;
     let mut circle = input.clone();
     for _ in 0..100 {
         circle.round();
@@ -77,11 +125,12 @@ pub fn day23_part1(input: &CupCircle) -> String {
         cup = circle.next_cup[cup];
         write!(answer, "{}", cup).unwrap();
     }
-    answer
+    Ok(answer)
 }
 
 #[aoc(day23, part2)]
-pub fn day23_part2(input: &CupCircle) -> usize {
+pub fn day23_part2(input: &CupCircle) -> Result<usize, SolveError> {
+    input.check()?;
     let mut circle = input.clone();
     let first = circle.current_cup;
     let last = circle
@@ -98,7 +147,7 @@ pub fn day23_part2(input: &CupCircle) -> usize {
     }
     let next_one = circle.next_cup[1];
     let next_two = circle.next_cup[next_one];
-    next_one * next_two
+    Ok(next_one * next_two)
 }
 
 #[cfg(test)]
@@ -107,7 +156,7 @@ mod tests {
 
     #[test]
     fn one_round() {
-        let mut circle = input_generator("389125467");
+        let mut circle = input_generator("389125467").unwrap();
         for i in 1..=10 {
             println!("-- move {} --", i);
             println!("cups: {}", circle);
@@ -120,7 +169,7 @@ mod tests {
 
     #[test]
     fn part2() {
-        let circle = input_generator("389125467");
-        assert_eq!(149245887792, day23_part2(&circle));
+        let circle = input_generator("389125467").unwrap();
+        assert_eq!(Ok(149245887792), day23_part2(&circle));
     }
 }
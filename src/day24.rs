@@ -1,10 +1,12 @@
-use std::{
-    collections::HashSet,
-    ops::Add,
-};
+use std::{collections::HashSet, ops::Add};
 
 use aoc_runner_derive::*;
 
+use crate::{
+    automaton::{Automaton, Coordinate, Rule},
+    ParseError, SolveError,
+};
+
 pub type Instruction = Vec<Direction>;
 
 #[derive(Debug, Clone, Copy)]
@@ -36,10 +38,16 @@ pub struct HexPos {
     pub east: i32,
 }
 
-impl HexPos {
-    pub fn neighbours(self) -> impl Iterator<Item = Self> {
+impl Coordinate for HexPos {
+    type Bounds = ();
+
+    fn neighbours(self) -> impl Iterator<Item = Self> {
         Direction::all().into_iter().map(move |dir| self + dir)
     }
+
+    fn empty_bounds() {}
+
+    fn expand(self, _bounds: &mut ()) {}
 }
 
 impl Add<Direction> for HexPos {
@@ -76,87 +84,113 @@ impl Add<Direction> for HexPos {
 }
 
 #[aoc_generator(day24)]
-pub fn input_generator(input: &str) -> Vec<Instruction> {
+pub fn input_generator(input: &str) -> Result<Vec<Instruction>, ParseError> {
     input
         .lines()
         .map(|line| {
-            let mut chars = line.chars();
+            let mut chars = line.char_indices();
             let mut directions = Vec::new();
-            while let Some(c) = chars.next() {
+            while let Some((i, c)) = chars.next() {
+                let err = |message| Err(ParseError::at(24, input, &line[i..], message));
                 let dir = match c {
                     'e' => Direction::East,
                     'w' => Direction::West,
                     'n' => match chars.next() {
-                        Some('e') => Direction::NorthEast,
-                        Some('w') => Direction::NorthWest,
-                        _ => panic!("Unexpected character after 'n'"),
+                        Some((_, 'e')) => Direction::NorthEast,
+                        Some((_, 'w')) => Direction::NorthWest,
+                        _ => return err("Unexpected character after 'n'".to_owned()),
                     },
                     's' => match chars.next() {
-                        Some('e') => Direction::SouthEast,
-                        Some('w') => Direction::SouthWest,
-                        _ => panic!("Unexpected character after 's'"),
+                        Some((_, 'e')) => Direction::SouthEast,
+                        Some((_, 'w')) => Direction::SouthWest,
+                        _ => return err("Unexpected character after 's'".to_owned()),
                     },
-                    _ => panic!("Unexpected character"),
+                    c => return err(format!("Unexpected character {:?}", c)),
                 };
                 directions.push(dir);
             }
-            directions
+            Ok(directions)
         })
         .collect()
 }
 
-#[aoc(day24, part1)]
-pub fn day24_part1(input: &[Instruction]) -> usize {
+fn flipped_tiles(input: &[Instruction]) -> HashSet<HexPos> {
     let mut flipped_tiles = HashSet::new();
     for instruction in input {
         let pos = instruction
             .iter()
             .copied()
             .fold(HexPos::default(), |pos, dir| pos + dir);
-        if flipped_tiles.contains(&pos) {
-            flipped_tiles.remove(&pos);
-        } else {
+        if !flipped_tiles.remove(&pos) {
             flipped_tiles.insert(pos);
         }
     }
-    flipped_tiles.len()
+    flipped_tiles
 }
 
+#[aoc(day24, part1)]
+pub fn day24_part1(input: &[Instruction]) -> Result<usize, SolveError> {
+    Ok(flipped_tiles(input).len())
+}
+
+/// Black tiles with zero or more than two black neighbours turn white, and white tiles with exactly
+/// two black neighbours turn black.
 #[aoc(day24, part2)]
-pub fn day24_part2(input: &[Instruction]) -> usize {
-    let mut flipped_tiles = HashSet::new();
-    for instruction in input {
-        let pos = instruction
-            .iter()
-            .copied()
-            .fold(HexPos::default(), |pos, dir| pos + dir);
-        if flipped_tiles.contains(&pos) {
-            flipped_tiles.remove(&pos);
-        } else {
-            flipped_tiles.insert(pos);
-        }
+pub fn day24_part2(input: &[Instruction]) -> Result<usize, SolveError> {
+    let floor = Automaton::new(Rule::new([2], [1, 2]), flipped_tiles(input));
+    Ok(floor.run(100).len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+        sesenwnenenewseeswwswswwnenewsewsw\n\
+        neeenesenwnwwswnenewnwwsewnenwseswesw\n\
+        seswneswswsenwwnwse\n\
+        nwnwneseeswswnenewneswwnewseswneseene\n\
+        swweswneswnenwsewnwneneseenw\n\
+        eesenwseswswnenwswnwnwsewwnwsene\n\
+        sewnenenenesenwsewnenwwwse\n\
+        wenwwweseeeweswwwnwwe\n\
+        wsweesenenewnwwnwsenewsenwwsesesenwne\n\
+        neeswseenwwswnwswswnw\n\
+        nenwswwsewswnenenewsenwsenwnesesenew\n\
+        enewnwewneswsewnwswenweswnenwsenwsw\n\
+        sweneswneswneneenwnewenewwneswswnese\n\
+        swwesenesewenwneswnwwneseswwne\n\
+        enesenwswwswneneswsenwnewswseenwsese\n\
+        wnwnesenesenenwwnenwsewesewsesesew\n\
+        nenewswnwewswnenesenwnesewesw\n\
+        eneswnwswnwsenenwnwnwwseeswneewsenese\n\
+        neswnwewnwnwseenwseesewsenwsweewe\n\
+        wseweeenwnesenwwwswnew\n\
+    ";
+
+    #[test]
+    fn example() {
+        let input = input_generator(EXAMPLE).unwrap();
+        assert_eq!(Ok(10), day24_part1(&input));
+        assert_eq!(Ok(2208), day24_part2(&input));
     }
-    for _ in 1..=100 {
-        let old_tiles = flipped_tiles.clone();
-        for &black_tile in &old_tiles {
-            let mut neighbour_count = 0;
-            for neighbour in black_tile.neighbours() {
-                if old_tiles.contains(&neighbour) {
-                    neighbour_count += 1;
-                } else {
-                    let black_neighbours = neighbour
-                        .neighbours()
-                        .filter(|pos| old_tiles.contains(pos))
-                        .count();
-                    if black_neighbours == 2 {
-                        flipped_tiles.insert(neighbour);
-                    }
-                }
-            }
-            if neighbour_count == 0 || neighbour_count > 2 {
-                flipped_tiles.remove(&black_tile);
-            }
+
+    #[test]
+    fn neighbours() {
+        let origin = HexPos::default();
+        let neighbours: HashSet<_> = origin.neighbours().collect();
+        assert_eq!(6, neighbours.len());
+        assert!(!neighbours.contains(&origin));
+        for dir in Direction::all() {
+            assert!(origin.neighbours().any(|pos| pos == origin + dir));
         }
+        // nwwswee leads back to the start, and esew to the south-east neighbour.
+        let path = |directions: &str| {
+            input_generator(directions).unwrap()[0]
+                .iter()
+                .fold(origin, |pos, &dir| pos + dir)
+        };
+        assert_eq!(origin, path("nwwswee"));
+        assert_eq!(origin + Direction::SouthEast, path("esew"));
     }
-    flipped_tiles.len()
 }
@@ -1,5 +1,7 @@
 use aoc_runner_derive::*;
 
+use crate::{ParseError, SolveError};
+
 #[derive(Debug, Clone, Copy)]
 pub struct Keys {
     card: u64,
@@ -7,23 +9,37 @@ pub struct Keys {
 }
 
 #[aoc_generator(day25)]
-pub fn input_generator(input: &str) -> Keys {
-    let (card, door) = input.split_once('\n').unwrap();
-    Keys {
-        card: card.trim().parse().unwrap(),
-        door: door.trim().parse().unwrap(),
-    }
+pub fn input_generator(input: &str) -> Result<Keys, ParseError> {
+    let (card, door) = input
+        .split_once('\n')
+        .ok_or_else(|| ParseError::at(25, input, input, "Expected two public keys"))?;
+    let parse_key = |key: &str| {
+        let key = key.trim();
+        key.parse()
+            .map_err(|e| ParseError::at(25, input, key, format!("Invalid public key: {}", e)))
+    };
+    Ok(Keys {
+        card: parse_key(card)?,
+        door: parse_key(door)?,
+    })
 }
 
 #[aoc(day25, part1)]
-pub fn day25_part1(&input: &Keys) -> u64 {
-    let mut keys = std::iter::successors(Some(1), |key| Some((key * 7) % 20201227)).enumerate();
-    let (card_loop_size, _) = keys.clone().find(|&(_, key)| key == input.card).unwrap();
-    let (door_loop_size, _) = keys.clone().find(|&(_, key)| key == input.door).unwrap();
+pub fn day25_part1(&input: &Keys) -> Result<u64, SolveError> {
+    let keys = std::iter::successors(Some(1), |key| Some((key * 7) % 20201227))
+        .take(20201226)
+        .enumerate();
+    let loop_size = |public_key| {
+        keys.clone()
+            .find(|&(_, key)| key == public_key)
+            .ok_or_else(|| SolveError::NoSolution(format!("No loop size gives {}", public_key)))
+    };
+    loop_size(input.card)?;
+    let (door_loop_size, _) = loop_size(input.door)?;
 
     let mut encryption_key = 1;
     for _ in 0..door_loop_size {
         encryption_key = (encryption_key * input.card) % 20201227;
     }
-    encryption_key
+    Ok(encryption_key)
 }
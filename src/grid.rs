@@ -0,0 +1,449 @@
+use std::{
+    fmt,
+    ops::{Index, IndexMut},
+};
+
+use crate::ParseError;
+
+pub type Pos = (usize, usize);
+
+/// A cell type that can be read from and written to a character map.
+pub trait Cell: Sized {
+    fn from_char(c: char) -> Option<Self>;
+    fn to_char(&self) -> char;
+}
+
+impl Cell for bool {
+    fn from_char(c: char) -> Option<Self> {
+        match c {
+            '#' => Some(true),
+            '.' => Some(false),
+            _ => None,
+        }
+    }
+
+    fn to_char(&self) -> char {
+        if *self {
+            '#'
+        } else {
+            '.'
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Connectivity {
+    Four,
+    Eight,
+}
+
+impl Connectivity {
+    pub fn offsets(self) -> &'static [(isize, isize)] {
+        match self {
+            Connectivity::Four => &[(-1, 0), (0, -1), (0, 1), (1, 0)],
+            Connectivity::Eight => &[
+                (-1, -1),
+                (-1, 0),
+                (-1, 1),
+                (0, -1),
+                (0, 1),
+                (1, -1),
+                (1, 0),
+                (1, 1),
+            ],
+        }
+    }
+}
+
+/// One of the eight rotations and reflections of a rectangle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Transform {
+    Identity,
+    /// Rotate 90° clockwise.
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    /// Mirror left to right.
+    FlipHorizontal,
+    /// Mirror top to bottom.
+    FlipVertical,
+    /// Mirror along the top-left to bottom-right diagonal.
+    Transpose,
+    /// Mirror along the top-right to bottom-left diagonal.
+    AntiTranspose,
+}
+
+impl Transform {
+    pub const ALL: [Transform; 8] = [
+        Transform::Identity,
+        Transform::Rotate90,
+        Transform::Rotate180,
+        Transform::Rotate270,
+        Transform::FlipHorizontal,
+        Transform::FlipVertical,
+        Transform::Transpose,
+        Transform::AntiTranspose,
+    ];
+
+    fn swaps_axes(self) -> bool {
+        matches!(
+            self,
+            Transform::Rotate90
+                | Transform::Rotate270
+                | Transform::Transpose
+                | Transform::AntiTranspose
+        )
+    }
+
+    /// Maps a position in the transformed view back to the grid of size `width` by `height`.
+    fn source(self, (row, col): Pos, width: usize, height: usize) -> Pos {
+        match self {
+            Transform::Identity => (row, col),
+            Transform::Rotate90 => (height - 1 - col, row),
+            Transform::Rotate180 => (height - 1 - row, width - 1 - col),
+            Transform::Rotate270 => (col, width - 1 - row),
+            Transform::FlipHorizontal => (row, width - 1 - col),
+            Transform::FlipVertical => (height - 1 - row, col),
+            Transform::Transpose => (col, row),
+            Transform::AntiTranspose => (height - 1 - col, width - 1 - row),
+        }
+    }
+}
+
+/// A rectangular grid of cells, indexed by `(row, col)`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    pub fn from_fn(width: usize, height: usize, mut f: impl FnMut(Pos) -> T) -> Self {
+        let cells = (0..height)
+            .flat_map(|row| (0..width).map(move |col| (row, col)))
+            .map(&mut f)
+            .collect();
+        Self {
+            width,
+            height,
+            cells,
+        }
+    }
+
+    /// Builds a grid from its rows, returning `None` if they aren't all the same length.
+    pub fn from_rows(rows: Vec<Vec<T>>) -> Option<Self> {
+        let height = rows.len();
+        let width = rows.first().map_or(0, Vec::len);
+        if rows.iter().any(|row| row.len() != width) {
+            return None;
+        }
+        Some(Self {
+            width,
+            height,
+            cells: rows.into_iter().flatten().collect(),
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn contains(&self, (row, col): Pos) -> bool {
+        row < self.height && col < self.width
+    }
+
+    pub fn get(&self, pos: Pos) -> Option<&T> {
+        if self.contains(pos) {
+            Some(&self.cells[pos.0 * self.width + pos.1])
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, pos: Pos) -> Option<&mut T> {
+        if self.contains(pos) {
+            Some(&mut self.cells[pos.0 * self.width + pos.1])
+        } else {
+            None
+        }
+    }
+
+    pub fn row(&self, row: usize) -> &[T] {
+        &self.cells[row * self.width..(row + 1) * self.width]
+    }
+
+    pub fn rows(&self) -> impl DoubleEndedIterator<Item = &[T]> + ExactSizeIterator + '_ {
+        (0..self.height).map(move |row| self.row(row))
+    }
+
+    pub fn column(&self, col: usize) -> impl DoubleEndedIterator<Item = &T> + '_ {
+        assert!(col < self.width, "Column {} out of bounds", col);
+        (0..self.height).map(move |row| &self[(row, col)])
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.cells.iter()
+    }
+
+    /// Iterates over every cell along with its position, row by row.
+    pub fn positions(&self) -> impl Iterator<Item = (Pos, &T)> + '_ {
+        let width = self.width;
+        self.cells
+            .iter()
+            .enumerate()
+            .map(move |(i, cell)| ((i / width, i % width), cell))
+    }
+
+    /// Moves from `pos` by `(rows, cols)`, returning `None` if that leaves the grid.
+    pub fn offset(&self, (row, col): Pos, (rows, cols): (isize, isize)) -> Option<Pos> {
+        let row = (row as isize).checked_add(rows)?;
+        let col = (col as isize).checked_add(cols)?;
+        if row < 0 || col < 0 {
+            return None;
+        }
+        let pos = (row as usize, col as usize);
+        if self.contains(pos) {
+            Some(pos)
+        } else {
+            None
+        }
+    }
+
+    pub fn neighbours(
+        &self,
+        pos: Pos,
+        connectivity: Connectivity,
+    ) -> impl Iterator<Item = Pos> + '_ {
+        connectivity
+            .offsets()
+            .iter()
+            .filter_map(move |&direction| self.offset(pos, direction))
+    }
+
+    /// The positions visited moving from `pos` in `direction` until leaving the grid, excluding
+    /// `pos` itself.
+    pub fn ray(&self, pos: Pos, direction: (isize, isize)) -> impl Iterator<Item = Pos> + '_ {
+        std::iter::successors(self.offset(pos, direction), move |&pos| {
+            self.offset(pos, direction)
+        })
+    }
+
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Grid<U> {
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: self.cells.iter().map(f).collect(),
+        }
+    }
+
+    pub fn view(&self, transform: Transform) -> View<'_, T> {
+        View {
+            grid: self,
+            transform,
+        }
+    }
+}
+
+impl<T: Cell> Grid<T> {
+    pub fn parse(day: u8, input: &str) -> Result<Self, ParseError> {
+        Self::parse_lines(day, input, input.lines())
+    }
+
+    /// Parses a grid from `lines`, which must all be slices of `input`, so errors can point at
+    /// the offending character.
+    pub fn parse_lines<'a>(
+        day: u8,
+        input: &str,
+        lines: impl IntoIterator<Item = &'a str>,
+    ) -> Result<Self, ParseError> {
+        let mut width = None;
+        let mut height = 0;
+        let mut cells = Vec::new();
+        for line in lines {
+            let start = cells.len();
+            for (i, c) in line.char_indices() {
+                let cell = T::from_char(c).ok_or_else(|| {
+                    ParseError::at(
+                        day,
+                        input,
+                        &line[i..],
+                        format!("Unexpected character {:?}", c),
+                    )
+                })?;
+                cells.push(cell);
+            }
+            let len = cells.len() - start;
+            if *width.get_or_insert(len) != len {
+                return Err(ParseError::at(
+                    day,
+                    input,
+                    line,
+                    format!("Expected {} cells in row, found {}", width.unwrap(), len),
+                ));
+            }
+            height += 1;
+        }
+        Ok(Self {
+            width: width.unwrap_or(0),
+            height,
+            cells,
+        })
+    }
+}
+
+impl<T> Index<Pos> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, pos: Pos) -> &Self::Output {
+        self.get(pos)
+            .unwrap_or_else(|| panic!("Position {:?} out of bounds", pos))
+    }
+}
+
+impl<T> IndexMut<Pos> for Grid<T> {
+    fn index_mut(&mut self, pos: Pos) -> &mut Self::Output {
+        self.get_mut(pos)
+            .unwrap_or_else(|| panic!("Position {:?} out of bounds", pos))
+    }
+}
+
+impl<T: Cell> fmt::Display for Grid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.view(Transform::Identity).fmt(f)
+    }
+}
+
+/// A rotated or reflected view of a grid.
+#[derive(Debug)]
+pub struct View<'a, T> {
+    grid: &'a Grid<T>,
+    transform: Transform,
+}
+
+impl<'a, T> Clone for View<'a, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T> Copy for View<'a, T> {}
+
+impl<'a, T> View<'a, T> {
+    pub fn width(&self) -> usize {
+        if self.transform.swaps_axes() {
+            self.grid.height
+        } else {
+            self.grid.width
+        }
+    }
+
+    pub fn height(&self) -> usize {
+        if self.transform.swaps_axes() {
+            self.grid.width
+        } else {
+            self.grid.height
+        }
+    }
+
+    pub fn get(&self, (row, col): Pos) -> Option<&'a T> {
+        if row < self.height() && col < self.width() {
+            let pos = self
+                .transform
+                .source((row, col), self.grid.width, self.grid.height);
+            Some(&self.grid[pos])
+        } else {
+            None
+        }
+    }
+
+    pub fn row(&self, row: usize) -> impl DoubleEndedIterator<Item = &'a T> + 'a {
+        assert!(row < self.height(), "Row {} out of bounds", row);
+        let view = *self;
+        (0..self.width()).map(move |col| view.get((row, col)).unwrap())
+    }
+
+    pub fn to_grid(&self) -> Grid<T>
+    where
+        T: Clone,
+    {
+        Grid::from_fn(self.width(), self.height(), |pos| self[pos].clone())
+    }
+}
+
+impl<'a, T> Index<Pos> for View<'a, T> {
+    type Output = T;
+
+    fn index(&self, pos: Pos) -> &Self::Output {
+        self.get(pos)
+            .unwrap_or_else(|| panic!("Position {:?} out of bounds", pos))
+    }
+}
+
+impl<'a, T: Cell> fmt::Display for View<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in 0..self.height() {
+            for cell in self.row(row) {
+                write!(f, "{}", cell.to_char())?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INPUT: &str = "\
+        #..\n\
+        ##.\n\
+    ";
+
+    #[test]
+    fn parse_and_display() {
+        let grid = Grid::<bool>::parse(0, INPUT).unwrap();
+        assert_eq!((3, 2), (grid.width(), grid.height()));
+        assert_eq!(INPUT, grid.to_string());
+        assert_eq!(
+            vec![true, true],
+            grid.column(0).copied().collect::<Vec<_>>()
+        );
+
+        let err = Grid::<bool>::parse(0, "#..\n#.\n").unwrap_err();
+        assert_eq!((2, 1), (err.line, err.column));
+        let err = Grid::<bool>::parse(0, "#..\n#x.\n").unwrap_err();
+        assert_eq!((2, 2), (err.line, err.column));
+    }
+
+    #[test]
+    fn views() {
+        let grid = Grid::<bool>::parse(0, INPUT).unwrap();
+        let render = |transform| grid.view(transform).to_string();
+        assert_eq!("##\n#.\n..\n", render(Transform::Rotate90));
+        assert_eq!(".##\n..#\n", render(Transform::Rotate180));
+        assert_eq!("..\n.#\n##\n", render(Transform::Rotate270));
+        assert_eq!("..#\n.##\n", render(Transform::FlipHorizontal));
+        assert_eq!("##.\n#..\n", render(Transform::FlipVertical));
+        assert_eq!("##\n.#\n..\n", render(Transform::Transpose));
+        assert_eq!("..\n#.\n##\n", render(Transform::AntiTranspose));
+    }
+
+    #[test]
+    fn neighbours_and_rays() {
+        let grid = Grid::from_fn(3, 3, |pos| pos);
+        assert_eq!(3, grid.neighbours((0, 0), Connectivity::Eight).count());
+        assert_eq!(4, grid.neighbours((1, 1), Connectivity::Four).count());
+        assert_eq!(8, grid.neighbours((1, 1), Connectivity::Eight).count());
+        assert_eq!(
+            vec![(1, 1), (2, 2)],
+            grid.ray((0, 0), (1, 1)).collect::<Vec<_>>()
+        );
+        assert_eq!(0, grid.ray((0, 0), (-1, 0)).count());
+    }
+}
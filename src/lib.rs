@@ -1,38 +1,211 @@
-#![feature(
-    array_windows,
-    bool_to_option,
-    map_first_last,
-    destructuring_assignment,
-    str_split_once,
-    linked_list_cursors
-)]
+use std::{borrow::Cow, error::Error, fmt};
 
 use aoc_runner_derive::*;
 
-mod day01;
-mod day02;
-mod day03;
-mod day04;
-mod day05;
-mod day06;
-mod day07;
-mod day08;
-mod day09;
-mod day10;
-mod day11;
-mod day12;
-mod day13;
-mod day14;
-mod day15;
-mod day16;
-mod day17;
-mod day18;
-mod day19;
-mod day20;
-mod day21;
-mod day22;
-mod day23;
-mod day24;
-mod day25;
+pub mod automaton;
+pub mod day01;
+pub mod day02;
+pub mod day03;
+pub mod day04;
+pub mod day05;
+pub mod day06;
+pub mod day07;
+pub mod day08;
+pub mod day09;
+pub mod day10;
+pub mod day11;
+pub mod day12;
+pub mod day13;
+pub mod day14;
+pub mod day15;
+pub mod day16;
+pub mod day17;
+pub mod day18;
+pub mod day19;
+pub mod day20;
+pub mod day21;
+pub mod day22;
+pub mod day23;
+pub mod day24;
+pub mod day25;
+pub mod grid;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub day: u8,
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl ParseError {
+    pub fn new(day: u8, line: usize, column: usize, message: impl Into<String>) -> Self {
+        Self {
+            day,
+            line,
+            column,
+            message: message.into(),
+        }
+    }
+
+    /// Creates an error pointing at the start of `part`, which must be a slice of `input`.
+    ///
+    /// Line and column numbers are 1-based, columns are counted in characters.
+    pub fn at(day: u8, input: &str, part: &str, message: impl Into<String>) -> Self {
+        let offset = (part.as_ptr() as usize)
+            .checked_sub(input.as_ptr() as usize)
+            .filter(|&offset| offset <= input.len())
+            .unwrap_or(input.len());
+        let before = &input[..offset];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        Self::new(
+            day,
+            before.matches('\n').count() + 1,
+            before[line_start..].chars().count() + 1,
+            message,
+        )
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Day {} input, line {}, column {}: {}",
+            self.day, self.line, self.column, self.message
+        )
+    }
+}
+
+impl Error for ParseError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SolveError {
+    NoSolution(String),
+    Ambiguous(String),
+    InvalidInput(String),
+}
+
+impl fmt::Display for SolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SolveError::NoSolution(reason) => write!(f, "No solution: {}", reason),
+            SolveError::Ambiguous(reason) => write!(f, "Ambiguous solution: {}", reason),
+            SolveError::InvalidInput(reason) => {
+                write!(f, "Input violates puzzle invariant: {}", reason)
+            }
+        }
+    }
+}
+
+impl Error for SolveError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RunError {
+    UnknownPuzzle { day: u8, part: u8 },
+    Parse(ParseError),
+    Solve(SolveError),
+}
+
+impl fmt::Display for RunError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RunError::UnknownPuzzle { day, part } => {
+                write!(f, "There is no puzzle for day {} part {}", day, part)
+            }
+            RunError::Parse(e) => e.fmt(f),
+            RunError::Solve(e) => e.fmt(f),
+        }
+    }
+}
+
+impl Error for RunError {}
+
+impl From<ParseError> for RunError {
+    fn from(e: ParseError) -> Self {
+        RunError::Parse(e)
+    }
+}
+
+impl From<SolveError> for RunError {
+    fn from(e: SolveError) -> Self {
+        RunError::Solve(e)
+    }
+}
+
+/// Converts `\r\n` line endings to `\n`, for inputs that split on blank lines.
+pub fn normalise_line_endings(input: &str) -> Cow<'_, str> {
+    if input.contains('\r') {
+        Cow::Owned(input.replace("\r\n", "\n"))
+    } else {
+        Cow::Borrowed(input)
+    }
+}
+
+/// Strips the trailing line endings from puzzle input, whether they're `\n` or `\r\n`.
+pub fn trim_input(input: &str) -> &str {
+    input.trim_end_matches(&['\r', '\n'][..])
+}
+
+macro_rules! solutions {
+    ($($day:literal => $module:ident { $($part:literal => $solver:ident),* }),* $(,)?) => {
+        /// Parses `input` and solves the given part, without going through `cargo aoc`.
+        ///
+        /// Trailing line endings are ignored, as they are by the `cargo aoc` runner, and `\r\n`
+        /// line endings are read as `\n`.
+        pub fn solve(day: u8, part: u8, input: &str) -> Result<String, RunError> {
+            let input = normalise_line_endings(input);
+            let input = trim_input(&input);
+            match (day, part) {
+                $($(($day, $part) => {
+                    let parsed = $module::input_generator(input)?;
+                    Ok($module::$solver(&parsed)?.to_string())
+                })*)*
+                _ => Err(RunError::UnknownPuzzle { day, part }),
+            }
+        }
+    };
+}
+
+solutions! {
+    1 => day01 { 1 => day1_part1, 2 => day1_part2 },
+    2 => day02 { 1 => day2_part1, 2 => day2_part2 },
+    3 => day03 { 1 => day3_part1, 2 => day3_part2 },
+    4 => day04 { 1 => day4_part1, 2 => day4_part2 },
+    5 => day05 { 1 => day5_part1, 2 => day5_part2 },
+    6 => day06 { 1 => day6_part1, 2 => day6_part2 },
+    7 => day07 { 1 => day7_part1, 2 => day7_part2 },
+    8 => day08 { 1 => day8_part1, 2 => day8_part2 },
+    9 => day09 { 1 => day9_part1, 2 => day9_part2 },
+    10 => day10 { 1 => day10_part1, 2 => day10_part2 },
+    11 => day11 { 1 => day11_part1, 2 => day11_part2 },
+    12 => day12 { 1 => day12_part1, 2 => day12_part2 },
+    13 => day13 { 1 => day13_part1, 2 => day13_part2 },
+    14 => day14 { 1 => day14_part1, 2 => day14_part2 },
+    15 => day15 { 1 => day15_part1, 2 => day15_part2 },
+    16 => day16 { 1 => day16_part1, 2 => day16_part2 },
+    17 => day17 { 1 => day17_part1, 2 => day17_part2 },
+    18 => day18 { 1 => day18_part1, 2 => day18_part2 },
+    19 => day19 { 1 => day19_part1, 2 => day19_part2 },
+    20 => day20 { 1 => day20_part1, 2 => day20_part2 },
+    21 => day21 { 1 => day21_part1, 2 => day21_part2 },
+    22 => day22 { 1 => day22_part1, 2 => day22_part2 },
+    23 => day23 { 1 => day23_part1, 2 => day23_part2 },
+    24 => day24 { 1 => day24_part1, 2 => day24_part2 },
+    25 => day25 { 1 => day25_part1 },
+}
 
 aoc_lib! { year = 2020 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crlf_input() {
+        let input = "1721\r\n979\r\n366\r\n299\r\n675\r\n1456\r\n";
+        assert_eq!(Ok("514579".to_owned()), solve(1, 1, input));
+        assert_eq!("1721\r\n979", trim_input("1721\r\n979\r\n\r\n"));
+        assert_eq!("a\n\nb\r", normalise_line_endings("a\r\n\r\nb\r"));
+    }
+}
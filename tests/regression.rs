@@ -0,0 +1,147 @@
+//! Runs every puzzle input in `inputs/dayNN.txt` and compares the answers against those recorded
+//! in `answers/dayNN.toml`, which look like:
+//!
+//! ```toml
+//! part1 = "295"
+//! part2 = 1068781
+//! ```
+//!
+//! The puzzle examples in `tests/examples` are checked the same way on every run. Personal inputs
+//! aren't checked in, so `inputs/` is skipped if it doesn't exist, but every input in it must have
+//! recorded answers. Parts without a recorded answer are skipped. Set `AOC2020_RECORD_ANSWERS=1`
+//! to (re)write the answer files in `answers/` from the current solutions.
+
+use std::{
+    collections::BTreeMap,
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+fn parse_answers(path: &Path, contents: &str) -> BTreeMap<u8, String> {
+    let mut answers = BTreeMap::new();
+    for (i, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fail = |message: &str| -> ! {
+            panic!("{}:{}: {}: {:?}", path.display(), i + 1, message, line)
+        };
+        let (key, value) = line
+            .split_once('=')
+            .unwrap_or_else(|| fail("Expected `partN = answer`"));
+        let part = key
+            .trim()
+            .strip_prefix("part")
+            .and_then(|part| part.parse().ok())
+            .unwrap_or_else(|| fail("Expected key part1 or part2"));
+        let value = value.trim();
+        let value = match value.strip_prefix('"') {
+            Some(value) => value
+                .strip_suffix('"')
+                .unwrap_or_else(|| fail("Unterminated string"))
+                .to_owned(),
+            None if value.parse::<i128>().is_ok() => value.to_owned(),
+            None => fail("Expected a string or integer answer"),
+        };
+        answers.insert(part, value);
+    }
+    answers
+}
+
+/// Checks every input in `inputs` that has answers in `answers`, returning how many answers were
+/// checked and describing every one that differs.
+fn check_answers(inputs: &Path, answers: &Path, record: bool) -> (usize, Vec<String>) {
+    let mut checked = 0;
+    let mut failures = Vec::new();
+    for day in 1..=25u8 {
+        let input_path = inputs.join(format!("day{:02}.txt", day));
+        let answers_path = answers.join(format!("day{:02}.toml", day));
+        let input = match fs::read_to_string(&input_path) {
+            Ok(input) => input,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => panic!("Couldn't read {}: {}", input_path.display(), e),
+        };
+        let parts: &[u8] = if day == 25 { &[1] } else { &[1, 2] };
+
+        if record {
+            let mut contents = String::new();
+            for &part in parts {
+                match aoc2020::solve(day, part, &input) {
+                    Ok(answer) => contents += &format!("part{} = {:?}\n", part, answer),
+                    Err(e) => failures.push(format!("day {} part {}: {}", day, part, e)),
+                }
+            }
+            fs::create_dir_all(answers).unwrap();
+            fs::write(&answers_path, contents).unwrap();
+            continue;
+        }
+
+        let recorded = match fs::read_to_string(&answers_path) {
+            Ok(contents) => parse_answers(&answers_path, &contents),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                failures.push(format!(
+                    "day {}: {} has no answers in {}",
+                    day,
+                    input_path.display(),
+                    answers_path.display()
+                ));
+                continue;
+            }
+            Err(e) => panic!("Couldn't read {}: {}", answers_path.display(), e),
+        };
+        for &part in parts {
+            let expected = match recorded.get(&part) {
+                Some(expected) => expected,
+                None => continue,
+            };
+            checked += 1;
+            match aoc2020::solve(day, part, &input) {
+                Ok(answer) if &answer == expected => {}
+                Ok(answer) => failures.push(format!(
+                    "day {} part {}: expected {}, got {}",
+                    day, part, expected, answer
+                )),
+                Err(e) => failures.push(format!(
+                    "day {} part {}: expected {}, got error: {}",
+                    day, part, expected, e
+                )),
+            }
+        }
+    }
+    (checked, failures)
+}
+
+fn assert_checked(what: &Path, (checked, failures): (usize, Vec<String>)) {
+    assert!(
+        failures.is_empty(),
+        "{} answers differ:\n{}",
+        failures.len(),
+        failures.join("\n")
+    );
+    assert!(checked > 0, "No answers checked in {}", what.display());
+    eprintln!("Checked {} answers in {}", checked, what.display());
+}
+
+#[test]
+fn examples() {
+    let examples = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/examples");
+    assert_checked(&examples, check_answers(&examples, &examples, false));
+}
+
+#[test]
+fn recorded_answers() {
+    let root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let inputs = root.join("inputs");
+    if !inputs.is_dir() {
+        eprintln!("Skipping recorded answers: no {}", inputs.display());
+        return;
+    }
+    let record = env::var_os("AOC2020_RECORD_ANSWERS").is_some();
+    let result = check_answers(&inputs, &root.join("answers"), record);
+    if !record {
+        assert_checked(&inputs, result);
+    } else {
+        assert!(result.1.is_empty(), "{}", result.1.join("\n"));
+    }
+}